@@ -2,9 +2,13 @@
 // Author: Nocthir <nocthir@proton.me>
 // SPDX-License-Identifier: MIT or Apache-2.0
 
+use std::io;
+
 use criterion::{Criterion, criterion_group, criterion_main};
 use std::hint::black_box;
-use worgen_rs::{data::archive, settings};
+use worgen_rs::{assets::archive::*, data::archive, settings};
+use wow_adt as adt;
+use wow_mpq as mpq;
 
 fn bench_read_terrain_archive(c: &mut Criterion) {
     // Load settings from settings.json
@@ -26,5 +30,87 @@ fn bench_read_terrain_archive(c: &mut Criterion) {
     });
 }
 
-criterion_group!(benches, bench_read_terrain_archive);
+fn bench_read_tile(c: &mut Criterion) {
+    let settings = match settings::TestSettings::load() {
+        Ok(s) => s,
+        Err(e) => {
+            println!("[BENCH] Failed to load settings: {e}");
+            return;
+        }
+    };
+    let registry = match settings::Settings::load() {
+        Ok(s) => {
+            let registry = ArchiveRegistry::default();
+            registry.set(FileArchiveMap::new(&s));
+            registry
+        }
+        Err(e) => {
+            println!("[BENCH] Failed to load settings: {e}");
+            return;
+        }
+    };
+
+    // A tile load reads the ADT and every texture, model and world model it references
+    let file_paths = match get_tile_file_paths(&registry, &settings.test_terrain_path) {
+        Ok(paths) => paths,
+        Err(e) => {
+            println!("[BENCH] Failed to read tile: {e}");
+            return;
+        }
+    };
+
+    c.bench_function("read_tile_unpooled", |b| {
+        b.iter(|| {
+            for file_path in &file_paths {
+                if let Err(e) = read_file_unpooled(&registry, black_box(file_path)) {
+                    println!("[BENCH] read_tile_unpooled error: {e}");
+                }
+            }
+        })
+    });
+
+    c.bench_function("read_tile_pooled", |b| {
+        b.iter(|| {
+            let reader = ArchiveAssetReader::new(registry.clone());
+            for file_path in &file_paths {
+                if let Err(e) = reader.read_file(black_box(file_path)) {
+                    println!("[BENCH] read_tile_pooled error: {e}");
+                }
+            }
+        })
+    });
+}
+
+fn get_tile_file_paths(
+    registry: &ArchiveRegistry,
+    tile_path: &str,
+) -> bevy::prelude::Result<Vec<String>> {
+    let bytes = ArchiveAssetReader::new(registry.clone()).read_file(tile_path)?;
+    let world_map = adt::Adt::from_reader(&mut io::Cursor::new(&bytes))?;
+
+    let mut file_paths = vec![tile_path.to_string()];
+    if let Some(mtex) = &world_map.mtex {
+        file_paths.extend(mtex.filenames.iter().cloned());
+    }
+    if let Some(mmdx) = &world_map.mmdx {
+        file_paths.extend(mmdx.filenames.iter().map(|f| f.replace(".mdx", ".m2")));
+    }
+    if let Some(mwmo) = &world_map.mwmo {
+        file_paths.extend(mwmo.filenames.iter().cloned());
+    }
+    Ok(file_paths)
+}
+
+/// Reads a file the way `ArchiveAssetReader` did before pooling archive handles.
+fn read_file_unpooled(
+    registry: &ArchiveRegistry,
+    file_path: &str,
+) -> bevy::prelude::Result<Vec<u8>> {
+    let file_archive_map = registry.get();
+    let archive_path = file_archive_map.get_archive_path(file_path)?;
+    let mut archive = mpq::Archive::open(archive_path)?;
+    Ok(archive.read_file(file_path)?)
+}
+
+criterion_group!(benches, bench_read_terrain_archive, bench_read_tile);
 criterion_main!(benches);
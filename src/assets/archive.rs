@@ -2,31 +2,49 @@
 // Author: Nocthir <nocthir@proton.me>
 // SPDX-License-Identifier: MIT or Apache-2.0
 
-use std::collections::HashMap;
-use std::io;
+use std::collections::{BTreeMap, HashMap};
 use std::path::{Path, PathBuf};
-use std::ptr::addr_of;
-use std::sync::Once;
+use std::sync::{Arc, Mutex, RwLock};
+use std::{fs, io};
 
+use bevy::asset::LoadContext;
 use bevy::asset::io::Reader;
 use bevy::asset::io::{
     AssetReader, AssetReaderError, AssetSource, AssetSourceId, PathStream, VecReader,
 };
 use bevy::prelude::*;
+use bevy::tasks::futures_lite::stream;
 use wow_mpq as mpq;
 
-use crate::settings;
+use crate::data::cache::{ArchiveIndex, ArchiveIndexCache, CACHE_PATH};
+use crate::data::listfile::Listfile;
+use crate::settings::Settings;
 
-pub static mut FILE_ARCHIVE_MAP: FileArchiveMap = FileArchiveMap::new();
-static FILE_ARCHIVE_MAP_ONCE: Once = Once::new();
+/// Asset source reading the archives of the current game installation.
+pub const ARCHIVE_SOURCE: &str = "archive";
+/// Asset source reading the archives of the game installation compared with
+/// the current one, see [`ComparisonRegistry`].
+pub const COMPARISON_SOURCE: &str = "comparison";
 
-pub fn get_archive_paths() -> Result<Vec<PathBuf>> {
-    let game_path = PathBuf::from(&settings::Settings::get().game_path);
-    let data_path = game_path.join("Data");
+/// Returns the archives of the game data folder and of the active locale folder,
+/// sorted by load priority. Files found in later archives override the ones found
+/// in earlier archives.
+pub fn get_archive_paths(settings: &Settings) -> Result<Vec<PathBuf>> {
+    let data_path = get_data_path(settings);
 
+    let mut ret = get_archive_paths_in(&data_path)?;
+    if let Some(locale) = get_active_locale(settings)? {
+        ret.extend(get_archive_paths_in(data_path.join(locale))?);
+    }
+
+    sort_archive_paths(&mut ret);
+    Ok(ret)
+}
+
+fn get_archive_paths_in<P: AsRef<Path>>(dir_path: P) -> Result<Vec<PathBuf>> {
     let mut ret = Vec::new();
 
-    for file in data_path.read_dir()? {
+    for file in dir_path.as_ref().read_dir()? {
         let file = file?;
         let file_path = file.path();
         if is_archive_extension(&file_path) {
@@ -37,69 +55,628 @@ pub fn get_archive_paths() -> Result<Vec<PathBuf>> {
     Ok(ret)
 }
 
+pub fn get_data_path(settings: &Settings) -> PathBuf {
+    PathBuf::from(&settings.game_path).join("Data")
+}
+
+/// Returns the names of the locale folders found in the game data folder, e.g. `enUS`.
+pub fn get_locales(settings: &Settings) -> Result<Vec<String>> {
+    let mut ret = Vec::new();
+
+    for entry in get_data_path(settings).read_dir()? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if entry.file_type()?.is_dir() && is_locale_name(&name) {
+            ret.push(name);
+        }
+    }
+
+    ret.sort();
+    Ok(ret)
+}
+
+/// Returns the locale configured in the settings, or the first locale found in the
+/// game data folder when none is configured or the configured one is missing.
+/// Returns `None` when the game data folder has no locale folder.
+pub fn get_active_locale(settings: &Settings) -> Result<Option<String>> {
+    let locales = get_locales(settings)?;
+    if let Some(locale) = &settings.locale {
+        if let Some(locale) = locales.iter().find(|l| l.eq_ignore_ascii_case(locale)) {
+            return Ok(Some(locale.clone()));
+        }
+        warn!(
+            "Locale `{}` not found in game data folder, using {:?}",
+            locale,
+            locales.first()
+        );
+    }
+    Ok(locales.into_iter().next())
+}
+
+/// Sorts archive paths in the order the game client loads them:
+/// base archives, then locale archives, then patches, then locale patches.
+pub fn sort_archive_paths(archive_paths: &mut [PathBuf]) {
+    archive_paths.sort_by_cached_key(ArchivePriority::new);
+}
+
+/// Category of an archive in the client load order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ArchiveKind {
+    /// `common.MPQ`, `expansion.MPQ`, `lichking.MPQ`, ...
+    Base,
+    /// `locale-enUS.MPQ`, `speech-enUS.MPQ`, ...
+    Locale,
+    /// `patch.MPQ`, `patch-2.MPQ`, `patch-a.MPQ`, ...
+    Patch,
+    /// `patch-enUS.MPQ`, `patch-enUS-2.MPQ`, ...
+    LocalePatch,
+}
+
+/// Sort key of an archive, lower values are loaded first and overridden by higher values.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ArchivePriority {
+    pub kind: ArchiveKind,
+    pub rank: u32,
+    pub name: String,
+}
+
+impl ArchivePriority {
+    pub fn new<P: AsRef<Path>>(archive_path: P) -> Self {
+        let name = archive_path
+            .as_ref()
+            .file_stem()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_lowercase();
+        let (kind, rank) = Self::classify(&name);
+        Self { kind, rank, name }
+    }
+
+    fn classify(name: &str) -> (ArchiveKind, u32) {
+        if let Some(suffix) = name.strip_prefix("patch") {
+            return Self::classify_patch(suffix);
+        }
+
+        const BASE_ARCHIVES: [&str; 4] = ["common", "common-2", "expansion", "lichking"];
+        if let Some(rank) = BASE_ARCHIVES.iter().position(|base| *base == name) {
+            return (ArchiveKind::Base, rank as u32);
+        }
+
+        const LOCALE_PREFIXES: [&str; 6] = [
+            "locale-",
+            "speech-",
+            "expansion-locale-",
+            "expansion-speech-",
+            "lichking-locale-",
+            "lichking-speech-",
+        ];
+        if let Some(rank) = LOCALE_PREFIXES.iter().position(|prefix| {
+            name.strip_prefix(prefix)
+                .is_some_and(is_lowercase_locale_name)
+        }) {
+            return (ArchiveKind::Locale, rank as u32);
+        }
+        if name
+            .rsplit_once('-')
+            .is_some_and(|(_, locale)| is_lowercase_locale_name(locale))
+        {
+            return (ArchiveKind::Locale, LOCALE_PREFIXES.len() as u32);
+        }
+
+        // Unknown archives are loaded after the known base archives
+        (ArchiveKind::Base, BASE_ARCHIVES.len() as u32)
+    }
+
+    /// First rank of the lettered patches, which are loaded after all the numbered ones.
+    const LETTERED_PATCH_RANK: u32 = 1 << 16;
+
+    /// `suffix` is what follows `patch` in the archive name, e.g. `""`, `"-2"`, `"-a"`, `"-enus-3"`.
+    fn classify_patch(suffix: &str) -> (ArchiveKind, u32) {
+        let mut parts = suffix.split('-').skip(1);
+        let first = parts.next();
+        let second = parts.next();
+
+        let (kind, index) = match first {
+            Some(locale) if is_lowercase_locale_name(locale) => (ArchiveKind::LocalePatch, second),
+            _ => (ArchiveKind::Patch, first),
+        };
+
+        let rank = match index {
+            None => 1,
+            Some(index) => match index.parse::<u32>() {
+                Ok(number) => number.min(Self::LETTERED_PATCH_RANK - 1),
+                Err(_) => match index.as_bytes() {
+                    [letter] if letter.is_ascii_lowercase() => {
+                        Self::LETTERED_PATCH_RANK + (letter - b'a') as u32
+                    }
+                    _ => u32::MAX,
+                },
+            },
+        };
+        (kind, rank)
+    }
+}
+
+/// Whether `name` looks like a locale code such as `enUS` or `deDE`: a lowercase
+/// language code followed by an uppercase region code.
+pub fn is_locale_name(name: &str) -> bool {
+    matches!(
+        name.as_bytes(),
+        [a, b, c, d] if a.is_ascii_lowercase()
+            && b.is_ascii_lowercase()
+            && c.is_ascii_uppercase()
+            && d.is_ascii_uppercase()
+    )
+}
+
+/// Same as [`is_locale_name`] for names that were lowercased, such as the archive
+/// names in [`ArchivePriority`], where the case of the region code is lost.
+fn is_lowercase_locale_name(name: &str) -> bool {
+    name.len() == 4 && name.bytes().all(|c| c.is_ascii_lowercase())
+}
+
 pub fn is_archive_extension<P: AsRef<Path>>(path: P) -> bool {
     path.as_ref()
         .extension()
         .is_some_and(|ext| ext.to_string_lossy().eq_ignore_ascii_case("mpq"))
 }
 
+/// An archive, or the game data folder, that could not be read.
+#[derive(Debug, Clone)]
+pub struct ArchiveError {
+    pub path: PathBuf,
+    pub message: String,
+}
+
+impl ArchiveError {
+    pub fn new<P: AsRef<Path>, E: std::fmt::Display>(path: P, err: E) -> Self {
+        Self {
+            path: path.as_ref().into(),
+            message: err.to_string(),
+        }
+    }
+}
+
 #[derive(Default)]
 pub struct FileArchiveMap {
-    pub map: Option<HashMap<String, PathBuf>>,
+    /// Lowercase file path → archives containing it, sorted by load priority.
+    /// The last archive is the one the file is read from.
+    pub map: HashMap<String, Vec<PathBuf>>,
+    /// Virtual directory tree of all the files in the archives and in the overlay folder.
+    pub directories: DirectoryTree,
+    /// Lowercase file path → loose file overriding the archive contents.
+    pub overlay: HashMap<String, PathBuf>,
+    /// Listings and categorized file lists of the archives.
+    pub indices: ArchiveIndexCache,
+    /// File the archive index cache has been loaded from.
+    pub cache_path: PathBuf,
+    /// Archives successfully indexed, sorted by load priority.
+    pub archive_paths: Vec<PathBuf>,
+    /// Archives that could not be indexed. Their files are not available.
+    pub errors: Vec<ArchiveError>,
 }
 
 impl FileArchiveMap {
-    const fn new() -> Self {
-        Self { map: None }
-    }
-
-    pub fn get() -> &'static Self {
-        debug_assert!(FILE_ARCHIVE_MAP_ONCE.is_completed());
-        // SAFETY: no mut references exist at this point
-        unsafe { &*addr_of!(FILE_ARCHIVE_MAP) }
+    pub fn get_archive_path(&self, file_path: &str) -> Result<&PathBuf> {
+        self.get_archive_chain(file_path)?
+            .last()
+            .ok_or(format!("File `{}` not found in file archive map", file_path).into())
     }
 
-    pub fn get_archive_path(&self, file_path: &str) -> Result<&PathBuf> {
-        let lowercase_name = file_path.to_lowercase();
+    /// Returns all the archives containing `file_path`, sorted by load priority.
+    /// Every archive is shadowed by the ones following it.
+    pub fn get_archive_chain(&self, file_path: &str) -> Result<&[PathBuf]> {
         self.map
-            .as_ref()
-            .unwrap()
-            .get(&lowercase_name)
+            .get(&normalize_path(file_path))
+            .map(Vec::as_slice)
             .ok_or(format!("File `{}` not found in file archive map", file_path).into())
     }
 
-    fn fill(&mut self) -> Result<()> {
-        let mut map = HashMap::new();
-        for archive_path in get_archive_paths()? {
-            let mut archive = mpq::Archive::open(&archive_path)?;
-            for file_path in archive.list()? {
-                map.insert(file_path.name.to_lowercase(), archive_path.clone());
+    /// Returns the loose file overriding `file_path`, if any.
+    pub fn get_overlay_path(&self, file_path: &str) -> Option<&PathBuf> {
+        self.overlay.get(&normalize_path(file_path))
+    }
+
+    /// Indexes every archive of the game data folder configured in `settings`.
+    /// Archives that fail to open or list are recorded in `errors` and skipped.
+    pub fn new(settings: &Settings) -> Self {
+        Self::with_cache(settings, CACHE_PATH)
+    }
+
+    /// Same as [`FileArchiveMap::new`], with the archive index cache stored in `cache_path`.
+    pub fn with_cache<P: AsRef<Path>>(settings: &Settings, cache_path: P) -> Self {
+        let mut errors = Vec::new();
+
+        let archive_paths = get_archive_paths(settings).unwrap_or_else(|err| {
+            error!("Failed to find archives: {err}");
+            errors.push(ArchiveError::new(get_data_path(settings), err));
+            Vec::new()
+        });
+        let listfile = Listfile::new(settings, &mut errors);
+        let mut indices = ArchiveIndexCache::load(&cache_path);
+        if indices.update(&archive_paths, &listfile, &mut errors)
+            && let Err(err) = indices.save(&cache_path)
+        {
+            warn!("Failed to save archive index cache: {err}");
+        }
+
+        let mut map: HashMap<String, Vec<PathBuf>> = HashMap::new();
+        let mut directories = DirectoryTree::default();
+        let mut indexed_paths = Vec::new();
+        // Archive paths are sorted by priority, so the last pushed archive wins
+        for archive_path in archive_paths {
+            let Some(index) = indices.get(&archive_path) else {
+                continue;
+            };
+            for file_name in &index.file_names {
+                directories.insert_file(file_name);
+                map.entry(file_name.to_lowercase())
+                    .or_default()
+                    .push(archive_path.clone());
+            }
+            indexed_paths.push(archive_path);
+        }
+
+        let overlay = get_overlay_files(settings).unwrap_or_else(|err| {
+            error!("Failed to read overlay folder: {err}");
+            let overlay_path = settings.overlay_path.clone();
+            errors.push(ArchiveError::new(overlay_path.unwrap_or_default(), err));
+            HashMap::new()
+        });
+        for file_path in overlay.keys() {
+            directories.insert_file(file_path);
+        }
+
+        Self {
+            map,
+            directories,
+            overlay,
+            indices,
+            cache_path: cache_path.as_ref().into(),
+            archive_paths: indexed_paths,
+            errors,
+        }
+    }
+
+    pub fn get_archive_index<P: AsRef<Path>>(&self, archive_path: P) -> Option<&ArchiveIndex> {
+        self.indices.get(archive_path.as_ref())
+    }
+
+    pub fn is_directory(&self, dir_path: &str) -> bool {
+        self.directories.is_directory(dir_path)
+    }
+
+    /// Returns the paths of the files matching a glob pattern, sorted by path.
+    /// See [`glob_match`] for the pattern syntax.
+    pub fn find_files(&self, pattern: &str) -> Vec<String> {
+        let pattern: Vec<char> = normalize_path(pattern).chars().collect();
+        let file_names = self
+            .archive_paths
+            .iter()
+            .filter_map(|archive_path| self.get_archive_index(archive_path))
+            .flat_map(|index| &index.file_names)
+            .chain(self.overlay.keys());
+
+        let mut ret = BTreeMap::new();
+        for file_name in file_names {
+            let lowercase_name = normalize_path(file_name);
+            if !ret.contains_key(&lowercase_name) {
+                let chars: Vec<char> = lowercase_name.chars().collect();
+                if glob_match_chars(&pattern, &chars) {
+                    ret.insert(lowercase_name, file_name.clone());
+                }
             }
         }
-        self.map.replace(map);
-        Ok(())
+        ret.into_values().collect()
     }
+}
 
-    pub fn init() {
-        // SAFETY: no concurrent static mut access due to std::Once
-        #[allow(static_mut_refs)]
-        FILE_ARCHIVE_MAP_ONCE.call_once(|| unsafe {
-            FILE_ARCHIVE_MAP
-                .fill()
-                .expect("Failed to fill file archive map");
-        });
+/// Shared handle to the [`FileArchiveMap`] of the current game installation.
+///
+/// The `archive://` asset reader holds a clone of this resource, so replacing
+/// the map switches every subsequent read to the new installation.
+#[derive(Resource, Clone, Default)]
+pub struct ArchiveRegistry {
+    current: Arc<RwLock<RegisteredMap>>,
+}
+
+#[derive(Default)]
+struct RegisteredMap {
+    generation: u64,
+    file_archive_map: Arc<FileArchiveMap>,
+}
+
+impl ArchiveRegistry {
+    pub fn get(&self) -> Arc<FileArchiveMap> {
+        self.current.read().unwrap().file_archive_map.clone()
+    }
+
+    /// Number of times the map was replaced, which tells whether data read
+    /// through the registry comes from the current installation.
+    pub fn generation(&self) -> u64 {
+        self.current.read().unwrap().generation
+    }
+
+    fn get_with_generation(&self) -> (Arc<FileArchiveMap>, u64) {
+        let current = self.current.read().unwrap();
+        (current.file_archive_map.clone(), current.generation)
+    }
+
+    pub fn set(&self, file_archive_map: FileArchiveMap) {
+        let mut current = self.current.write().unwrap();
+        current.generation += 1;
+        current.file_archive_map = Arc::new(file_archive_map);
+    }
+}
+
+/// Archive registry of the game installation compared with the current one,
+/// read by the `comparison://` asset source. Empty until one is selected.
+#[derive(Resource, Clone, Default)]
+pub struct ComparisonRegistry(pub ArchiveRegistry);
+
+/// Returns the asset path of an archive file in the asset source of the asset
+/// being loaded, so the dependencies of a file are read from the same game
+/// installation as the file.
+pub fn get_asset_path<S: std::fmt::Display>(
+    load_context: &LoadContext<'_>,
+    file_path: S,
+) -> String {
+    let source = load_context
+        .asset_path()
+        .source()
+        .as_str()
+        .unwrap_or(ARCHIVE_SOURCE);
+    format!("{source}://{file_path}")
+}
+
+/// Indexes the archives of the game installation configured in the settings.
+pub fn init_archive_registry(settings: Res<Settings>, registry: Res<ArchiveRegistry>) {
+    registry.set(FileArchiveMap::new(&settings));
+}
+
+/// Returns the loose files of the overlay folder configured in the settings,
+/// keyed by their lowercase path relative to the overlay folder.
+pub fn get_overlay_files(settings: &Settings) -> Result<HashMap<String, PathBuf>> {
+    let mut ret = HashMap::new();
+    let Some(overlay_path) = &settings.overlay_path else {
+        return Ok(ret);
+    };
+    let overlay_path = PathBuf::from(overlay_path);
+
+    let mut dir_paths = vec![overlay_path.clone()];
+    while let Some(dir_path) = dir_paths.pop() {
+        for entry in dir_path.read_dir()? {
+            let entry = entry?;
+            let file_path = entry.path();
+            if entry.file_type()?.is_dir() {
+                dir_paths.push(file_path);
+            } else if !is_archive_extension(&file_path)
+                && let Ok(relative_path) = file_path.strip_prefix(&overlay_path)
+            {
+                let relative_path = relative_path.to_string_lossy().replace('/', "\\");
+                ret.insert(relative_path.to_lowercase(), file_path);
+            }
+        }
+    }
+
+    info!(
+        "Found {} loose files in {}",
+        ret.len(),
+        overlay_path.display()
+    );
+    Ok(ret)
+}
+
+/// Converts a file path to the lowercase, backslash separated form used as key
+/// by [`FileArchiveMap`] and [`DirectoryTree`].
+pub fn normalize_path(file_path: &str) -> String {
+    file_path
+        .replace('/', "\\")
+        .trim_matches('\\')
+        .to_lowercase()
+}
+
+/// Matches a file path against a glob pattern, ignoring case and separators.
+/// `?` matches any character and `*` any sequence of characters within a
+/// directory, while `**` matches any sequence of characters across directories.
+pub fn glob_match(pattern: &str, file_path: &str) -> bool {
+    let pattern: Vec<char> = normalize_path(pattern).chars().collect();
+    let file_path: Vec<char> = normalize_path(file_path).chars().collect();
+    glob_match_chars(&pattern, &file_path)
+}
+
+fn glob_match_chars(pattern: &[char], file_path: &[char]) -> bool {
+    match pattern.first().copied() {
+        None => file_path.is_empty(),
+        Some('*') => {
+            let across_directories = pattern.get(1) == Some(&'*');
+            let rest = if across_directories {
+                &pattern[2..]
+            } else {
+                &pattern[1..]
+            };
+            // `**\` also matches no directory at all
+            if across_directories
+                && rest.first() == Some(&'\\')
+                && glob_match_chars(&rest[1..], file_path)
+            {
+                return true;
+            }
+            for i in 0..=file_path.len() {
+                if glob_match_chars(rest, &file_path[i..]) {
+                    return true;
+                }
+                if !across_directories && file_path.get(i) == Some(&'\\') {
+                    return false;
+                }
+            }
+            false
+        }
+        Some('?') => {
+            file_path.first().is_some_and(|&c| c != '\\')
+                && glob_match_chars(&pattern[1..], &file_path[1..])
+        }
+        Some(c) => {
+            file_path.first() == Some(&c) && glob_match_chars(&pattern[1..], &file_path[1..])
+        }
+    }
+}
+
+/// Virtual directory tree built from the archive listfiles.
+///
+/// Archives only store full file paths, so directories are implied by the
+/// paths of the files they contain.
+#[derive(Default)]
+pub struct DirectoryTree {
+    /// Lowercase directory path → lowercase child name → child path.
+    /// The root directory is the empty string.
+    directories: HashMap<String, BTreeMap<String, String>>,
+}
+
+impl DirectoryTree {
+    pub fn insert_file(&mut self, file_path: &str) {
+        let mut child = file_path;
+        loop {
+            let parent = child
+                .rsplit_once('\\')
+                .map(|(parent, _)| parent)
+                .unwrap_or_default();
+            let children = self.directories.entry(parent.to_lowercase()).or_default();
+            let lowercase_child = child.to_lowercase();
+            if children.contains_key(&lowercase_child) {
+                // The parent directories have already been inserted
+                return;
+            }
+            children.insert(lowercase_child, child.to_string());
+            if parent.is_empty() {
+                return;
+            }
+            child = parent;
+        }
+    }
+
+    pub fn is_directory(&self, dir_path: &str) -> bool {
+        self.directories.contains_key(&normalize_path(dir_path))
+    }
+
+    /// Returns the paths of the files and directories directly inside `dir_path`.
+    pub fn read_directory(&self, dir_path: &str) -> Option<impl Iterator<Item = &String>> {
+        self.directories
+            .get(&normalize_path(dir_path))
+            .map(|children| children.values())
     }
 }
 
+/// Pool of open archive handles, so the archive header and tables are parsed
+/// once per handle instead of once per read.
+///
+/// Handles are keyed by archive path, so archives of different game
+/// installations never share a handle. They are dropped when the registry the
+/// archives come from switches to another map, see [`ArchiveRegistry::generation`].
+///
+/// A handle is taken out of the pool for the duration of a read, so concurrent
+/// loader tasks reading from the same archive get their own handle.
+#[derive(Default)]
+pub struct ArchivePool {
+    handles: Mutex<PooledHandles>,
+}
+
 #[derive(Default)]
-pub struct ArchiveAssetReader {}
+struct PooledHandles {
+    /// Registry generation of the pooled handles.
+    generation: u64,
+    archives: HashMap<PathBuf, Vec<mpq::Archive>>,
+}
+
+impl ArchivePool {
+    /// Maximum number of idle handles kept open for a single archive.
+    const MAX_IDLE_HANDLES: usize = 8;
+
+    /// Reads a file with a handle of the archive, for the registry `generation`
+    /// the archive path was looked up in.
+    pub fn read_file<P: AsRef<Path>>(
+        &self,
+        generation: u64,
+        archive_path: P,
+        file_name: &str,
+    ) -> Result<Vec<u8>> {
+        let archive_path = archive_path.as_ref();
+        let mut archive = self.take(generation, archive_path)?;
+        let ret = archive.read_file(file_name);
+        self.release(generation, archive_path, archive);
+        Ok(ret?)
+    }
+
+    fn take(&self, generation: u64, archive_path: &Path) -> Result<mpq::Archive> {
+        let pooled = {
+            let mut handles = self.handles.lock().unwrap();
+            if generation > handles.generation {
+                // The handles of the previous map are no longer needed
+                handles.archives.clear();
+                handles.generation = generation;
+            }
+            if generation == handles.generation {
+                handles.archives.get_mut(archive_path).and_then(Vec::pop)
+            } else {
+                None
+            }
+        };
+        match pooled {
+            Some(archive) => Ok(archive),
+            None => Ok(mpq::Archive::open(archive_path)?),
+        }
+    }
+
+    fn release(&self, generation: u64, archive_path: &Path, archive: mpq::Archive) {
+        let mut handles = self.handles.lock().unwrap();
+        if generation != handles.generation {
+            // Reads of an outdated map close their handle
+            return;
+        }
+        let archives = handles
+            .archives
+            .entry(archive_path.to_path_buf())
+            .or_default();
+        if archives.len() < Self::MAX_IDLE_HANDLES {
+            archives.push(archive);
+        }
+    }
+}
+
+pub struct ArchiveAssetReader {
+    registry: ArchiveRegistry,
+    pool: ArchivePool,
+}
 
 impl ArchiveAssetReader {
+    pub fn new(registry: ArchiveRegistry) -> Self {
+        Self {
+            registry,
+            pool: ArchivePool::default(),
+        }
+    }
+
     pub fn read_file<P: AsRef<Path>>(&self, file_path: P) -> Result<Vec<u8>> {
         let file_name = file_path.as_ref().to_str().ok_or("Invalid file path")?;
-        let archive_path = FileArchiveMap::get().get_archive_path(file_name)?;
-        let mut archive = mpq::Archive::open(archive_path)?;
-        Ok(archive.read_file(file_name)?)
+        let (file_archive_map, generation) = self.registry.get_with_generation();
+        if let Some(overlay_path) = file_archive_map.get_overlay_path(file_name) {
+            return Ok(fs::read(overlay_path)?);
+        }
+        let archive_path = file_archive_map.get_archive_path(file_name)?;
+        self.pool
+            .read_file(generation, archive_path, &file_name.replace('/', "\\"))
+    }
+
+    /// Returns the children of a virtual directory as asset paths.
+    pub fn list_directory<P: AsRef<Path>>(&self, dir_path: P) -> Option<Vec<PathBuf>> {
+        let dir_name = dir_path.as_ref().to_string_lossy();
+        let file_archive_map = self.registry.get();
+        let children = file_archive_map.directories.read_directory(&dir_name)?;
+        Some(
+            children
+                .map(|child| PathBuf::from(child.replace('\\', "/")))
+                .collect(),
+        )
     }
 
     fn into_error(err: BevyError) -> AssetReaderError {
@@ -123,12 +700,17 @@ impl AssetReader for ArchiveAssetReader {
 
     async fn read_directory<'a>(
         &'a self,
-        _path: &'a Path,
+        path: &'a Path,
     ) -> Result<Box<PathStream>, AssetReaderError> {
-        unimplemented!()
+        let children = self
+            .list_directory(path)
+            .ok_or_else(|| AssetReaderError::NotFound(path.to_path_buf()))?;
+        let stream: Box<PathStream> = Box::new(stream::iter(children));
+        Ok(stream)
     }
-    async fn is_directory<'a>(&'a self, _path: &'a Path) -> Result<bool, AssetReaderError> {
-        unimplemented!()
+
+    async fn is_directory<'a>(&'a self, path: &'a Path) -> Result<bool, AssetReaderError> {
+        Ok(self.registry.get().is_directory(&path.to_string_lossy()))
     }
 }
 
@@ -136,9 +718,130 @@ pub struct ArchiveAssetReaderPlugin;
 
 impl Plugin for ArchiveAssetReaderPlugin {
     fn build(&self, app: &mut App) {
-        app.register_asset_source(
-            AssetSourceId::Name("archive".into()),
-            AssetSource::build().with_reader(|| Box::new(ArchiveAssetReader {})),
+        let registry = ArchiveRegistry::default();
+        let comparison_registry = ComparisonRegistry::default();
+        app.insert_resource(registry.clone())
+            .insert_resource(comparison_registry.clone())
+            .register_asset_source(
+                AssetSourceId::Name(ARCHIVE_SOURCE.into()),
+                AssetSource::build()
+                    .with_reader(move || Box::new(ArchiveAssetReader::new(registry.clone()))),
+            )
+            .register_asset_source(
+                AssetSourceId::Name(COMPARISON_SOURCE.into()),
+                AssetSource::build().with_reader(move || {
+                    Box::new(ArchiveAssetReader::new(comparison_registry.0.clone()))
+                }),
+            );
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_directory_tree() {
+        let mut tree = DirectoryTree::default();
+        tree.insert_file("World\\Maps\\Azeroth\\Azeroth_32_48.adt");
+        tree.insert_file("world\\maps\\azeroth\\Azeroth_32_49.adt");
+        tree.insert_file("World\\Maps\\Azeroth\\Azeroth_32_48.adt");
+
+        assert!(tree.is_directory(""));
+        assert!(tree.is_directory("world/maps/azeroth"));
+        assert!(tree.is_directory("World\\Maps\\"));
+        assert!(!tree.is_directory("world/maps/azeroth/azeroth_32_48.adt"));
+
+        let root: Vec<_> = tree.read_directory("").unwrap().collect();
+        assert_eq!(root, ["World"]);
+        let tiles: Vec<_> = tree.read_directory("WORLD/MAPS/AZEROTH").unwrap().collect();
+        assert_eq!(
+            tiles,
+            [
+                "World\\Maps\\Azeroth\\Azeroth_32_48.adt",
+                "world\\maps\\azeroth\\Azeroth_32_49.adt"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_glob_match() {
+        let path = "World\\Maps\\Azeroth\\Azeroth_32_48.adt";
+        assert!(glob_match("world/maps/azeroth/azeroth_32_48.adt", path));
+        assert!(glob_match("World\\Maps\\Azeroth\\*.adt", path));
+        assert!(glob_match("world/maps/*/azeroth_3?_48.adt", path));
+        assert!(glob_match("**/*.ADT", path));
+        assert!(glob_match("world/**/azeroth_*", path));
+        assert!(glob_match("world/maps/azeroth/**/*.adt", path));
+        assert!(!glob_match("world/*.adt", path));
+        assert!(!glob_match("world/maps/azeroth/*.wdt", path));
+        assert!(!glob_match("world/maps/azeroth/azeroth_32_4?", path));
+    }
+
+    #[test]
+    fn test_archive_priority() {
+        let mut paths: Vec<PathBuf> = [
+            "patch-enUS-2.MPQ",
+            "patch-a.MPQ",
+            "patch-10.MPQ",
+            "patch-3.MPQ",
+            "lichking.MPQ",
+            "patch.MPQ",
+            "locale-enUS.MPQ",
+            "patch-enUS.MPQ",
+            "common-2.MPQ",
+            "patch-2.MPQ",
+            "common.MPQ",
+            "expansion.MPQ",
+            "lichking-speech-enUS.MPQ",
+        ]
+        .iter()
+        .map(PathBuf::from)
+        .collect();
+        sort_archive_paths(&mut paths);
+        let names: Vec<_> = paths.iter().map(|p| p.to_str().unwrap()).collect();
+        assert_eq!(
+            names,
+            [
+                "common.MPQ",
+                "common-2.MPQ",
+                "expansion.MPQ",
+                "lichking.MPQ",
+                "locale-enUS.MPQ",
+                "lichking-speech-enUS.MPQ",
+                "patch.MPQ",
+                "patch-2.MPQ",
+                "patch-3.MPQ",
+                "patch-10.MPQ",
+                "patch-a.MPQ",
+                "patch-enUS.MPQ",
+                "patch-enUS-2.MPQ",
+            ]
         );
     }
+
+    #[test]
+    fn test_locales() {
+        assert!(is_locale_name("enUS"));
+        assert!(is_locale_name("deDE"));
+        assert!(!is_locale_name("Item"));
+        assert!(!is_locale_name("enus"));
+        assert!(!is_locale_name("enUSA"));
+
+        let game_path = std::env::temp_dir().join(format!("worgen-locales-{}", std::process::id()));
+        let data_path = game_path.join("Data");
+        for dir in ["Item", "enUS", "Cache"] {
+            fs::create_dir_all(data_path.join(dir)).unwrap();
+        }
+        let settings = Settings {
+            game_path: game_path.to_string_lossy().into_owned(),
+            ..Default::default()
+        };
+        let locales = get_locales(&settings);
+        let active_locale = get_active_locale(&settings);
+        fs::remove_dir_all(&game_path).unwrap();
+
+        assert_eq!(locales.unwrap(), ["enUS"]);
+        assert_eq!(active_locale.unwrap().as_deref(), Some("enUS"));
+    }
 }
@@ -4,14 +4,79 @@
 
 use bevy::asset::{AssetLoader, LoadContext, io::Reader};
 use bevy::asset::{ReadAssetBytesError, RenderAssetUsages};
+use bevy::image::{CompressedImageFormatSupport, CompressedImageFormats, ImageSampler};
 use bevy::prelude::*;
 use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+use image::{Rgba32FImage, RgbaImage, imageops};
+use serde::{Deserialize, Serialize};
 use std::io;
 use thiserror::Error;
 use wow_blp as blp;
+use wow_blp::types::{BlpContent, BlpImage};
 
-#[derive(Default)]
-pub struct ImageLoader;
+use crate::assets::archive;
+
+pub struct ImageLoader {
+    /// Block-compressed formats the GPU can sample, none without a GPU.
+    supported_formats: CompressedImageFormats,
+}
+
+impl FromWorld for ImageLoader {
+    fn from_world(world: &mut World) -> Self {
+        let supported_formats = world
+            .get_resource::<CompressedImageFormatSupport>()
+            .map(|support| support.0)
+            .unwrap_or(CompressedImageFormats::NONE);
+        Self { supported_formats }
+    }
+}
+
+#[derive(Serialize, Deserialize, Default, Debug, Clone)]
+pub struct ImageSettings {
+    pub sampler: ImageSampler,
+    pub color_space: ColorSpace,
+    /// Decodes DXTn compressed files to RGBA instead of keeping their blocks
+    /// as a BC texture, e.g. to read their pixels on the CPU.
+    pub force_decode: bool,
+}
+
+/// How the texels of an image are interpreted when sampled.
+#[derive(Serialize, Deserialize, Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorSpace {
+    /// Colour textures, converted from sRGB to linear when sampled.
+    #[default]
+    Srgb,
+    /// Masks and other data, sampled as stored.
+    Linear,
+}
+
+impl ColorSpace {
+    /// Returns the variant of a texture format for this colour space.
+    pub fn apply(self, format: TextureFormat) -> TextureFormat {
+        match self {
+            ColorSpace::Srgb => format.add_srgb_suffix(),
+            ColorSpace::Linear => format.remove_srgb_suffix(),
+        }
+    }
+
+    /// Converts a stored channel to linear light.
+    fn decode_channel(self, value: u8) -> f32 {
+        let value = value as f32 / 255.0;
+        match self {
+            ColorSpace::Srgb => Srgba::gamma_function(value),
+            ColorSpace::Linear => value,
+        }
+    }
+
+    /// Converts a channel in linear light back to its stored value.
+    fn encode_channel(self, value: f32) -> u8 {
+        let value = match self {
+            ColorSpace::Srgb => Srgba::gamma_function_inverse(value),
+            ColorSpace::Linear => value,
+        };
+        (value.clamp(0.0, 1.0) * 255.0).round() as u8
+    }
+}
 
 #[derive(Debug, Error)]
 pub enum ImageLoaderError {
@@ -23,11 +88,13 @@ pub enum ImageLoaderError {
     Conversion(#[from] blp::convert::Error),
     #[error("Read error: {0}")]
     Read(#[from] ReadAssetBytesError),
+    #[error("Decoded image does not match its size {0}×{1}")]
+    Size(u32, u32),
 }
 
 impl AssetLoader for ImageLoader {
     type Asset = Image;
-    type Settings = bevy::image::ImageLoaderSettings;
+    type Settings = ImageSettings;
     type Error = ImageLoaderError;
 
     async fn load(
@@ -38,8 +105,21 @@ impl AssetLoader for ImageLoader {
     ) -> Result<Self::Asset, Self::Error> {
         let mut bytes = Vec::new();
         reader.read_to_end(&mut bytes).await?;
-        let mut image = Self::load_image(&bytes)?;
+        let blp_image = blp::parser::load_blp_from_buf(&bytes)?;
+        let compressed = if settings.force_decode
+            || !self.supported_formats.contains(CompressedImageFormats::BC)
+        {
+            None
+        } else {
+            Self::load_compressed(&blp_image)
+        };
+        let mut image = match compressed {
+            Some(image) => image,
+            None => Self::decode(&blp_image, settings.color_space)?,
+        };
         image.sampler = settings.sampler.clone();
+        image.texture_descriptor.format =
+            settings.color_space.apply(image.texture_descriptor.format);
         Ok(image)
     }
 
@@ -53,23 +133,257 @@ impl ImageLoader {
         path: S,
         load_context: &mut LoadContext<'_>,
     ) -> Result<Image, ImageLoaderError> {
-        let asset_path = format!("archive://{}", path.into());
+        let asset_path = archive::get_asset_path(load_context, path.into());
         let bytes = load_context.read_asset_bytes(asset_path).await?;
-        Self::load_image(&bytes)
+        Self::decode_bytes(&bytes)
+    }
+
+    /// Decodes a BLP colour texture to an RGBA image, whatever its compression.
+    pub fn decode_bytes(bytes: &[u8]) -> Result<Image, ImageLoaderError> {
+        let blp_image = blp::parser::load_blp_from_buf(bytes)?;
+        Self::decode(&blp_image, ColorSpace::Srgb)
+    }
+
+    /// Keeps the DXTn blocks of every mip level stored in the file as a BC
+    /// texture. Missing levels cannot be generated without decoding, so the
+    /// chain stops at the first missing or malformed level. Returns `None` for
+    /// palettized, uncompressed and JPEG files, or when the size is not a
+    /// multiple of the block size.
+    fn load_compressed(blp_image: &BlpImage) -> Option<Image> {
+        let (texture_format, block_size, dxtn) = match &blp_image.content {
+            BlpContent::Dxt1(dxtn) => (TextureFormat::Bc1RgbaUnorm, 8, dxtn),
+            BlpContent::Dxt3(dxtn) => (TextureFormat::Bc2RgbaUnorm, 16, dxtn),
+            BlpContent::Dxt5(dxtn) => (TextureFormat::Bc3RgbaUnorm, 16, dxtn),
+            _ => return None,
+        };
+        let (width, height) = (blp_image.header.width, blp_image.header.height);
+        if width == 0 || !width.is_multiple_of(4) || height == 0 || !height.is_multiple_of(4) {
+            return None;
+        }
+
+        let mut data = Vec::new();
+        let mut mip_level_count = 0;
+        for (level, mip) in dxtn.images.iter().enumerate() {
+            let level = level as u32;
+            if level == get_mip_count(width, height)
+                || mip.content.len() != get_compressed_mip_size(width, height, level, block_size)
+            {
+                break;
+            }
+            data.extend_from_slice(&mip.content);
+            mip_level_count += 1;
+        }
+        if mip_level_count == 0 {
+            return None;
+        }
+
+        let extent = Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        };
+        let dimension = TextureDimension::D2;
+        let usage = RenderAssetUsages::MAIN_WORLD | RenderAssetUsages::RENDER_WORLD;
+        let mut image = Image::new_uninit(extent, dimension, texture_format, usage);
+        image.texture_descriptor.mip_level_count = mip_level_count;
+        image.data = Some(data);
+        Some(image)
     }
 
-    fn load_image(bytes: &[u8]) -> Result<Image, ImageLoaderError> {
-        let image = blp::parser::load_blp_from_buf(bytes)?;
-        let dyn_image = blp::convert::blp_to_image(&image, 0)?;
+    /// Decodes every mip level stored in the file, and generates the missing
+    /// ones down to 1×1 so the whole chain can be sampled trilinearly.
+    fn decode(blp_image: &BlpImage, color_space: ColorSpace) -> Result<Image, ImageLoaderError> {
+        let mut mips = Self::decode_stored_mips(blp_image)?;
+        generate_mips(&mut mips, color_space);
+
         let extent = Extent3d {
-            width: dyn_image.width(),
-            height: dyn_image.height(),
+            width: mips[0].width(),
+            height: mips[0].height(),
             depth_or_array_layers: 1,
         };
         let dimension = TextureDimension::D2;
-        let data = dyn_image.to_rgba8().into_raw();
         let texture_format = TextureFormat::Rgba8Unorm;
         let usage = RenderAssetUsages::MAIN_WORLD | RenderAssetUsages::RENDER_WORLD;
-        Ok(Image::new(extent, dimension, data, texture_format, usage))
+        let mut image = Image::new_uninit(extent, dimension, texture_format, usage);
+        image.texture_descriptor.mip_level_count = mips.len() as u32;
+        image.data = Some(mips.into_iter().flat_map(RgbaImage::into_raw).collect());
+        Ok(image)
+    }
+
+    /// Decodes the mip levels stored in the file, stopping at the first
+    /// missing or malformed one.
+    fn decode_stored_mips(blp_image: &BlpImage) -> Result<Vec<RgbaImage>, ImageLoaderError> {
+        let dyn_image = blp::convert::blp_to_image(blp_image, 0)?;
+        let (width, height) = (dyn_image.width(), dyn_image.height());
+        let mut mips = vec![Self::to_rgba(
+            dyn_image.to_rgba8().into_raw(),
+            width,
+            height,
+        )?];
+
+        for level in 1..get_mip_count(width, height) {
+            let (mip_width, mip_height) = get_mip_size(width, height, level);
+            match blp::convert::blp_to_image(blp_image, level as usize) {
+                Ok(mip) if mip.width() == mip_width && mip.height() == mip_height => {
+                    mips.push(Self::to_rgba(
+                        mip.to_rgba8().into_raw(),
+                        mip_width,
+                        mip_height,
+                    )?);
+                }
+                _ => break,
+            }
+        }
+        Ok(mips)
+    }
+
+    fn to_rgba(data: Vec<u8>, width: u32, height: u32) -> Result<RgbaImage, ImageLoaderError> {
+        RgbaImage::from_raw(width, height, data).ok_or(ImageLoaderError::Size(width, height))
+    }
+}
+
+/// Header information of a BLP file.
+#[derive(Debug, Clone)]
+pub struct BlpInfo {
+    pub compression: &'static str,
+    pub alpha_bits: u32,
+    pub width: u32,
+    pub height: u32,
+    /// Number of mip levels stored in the file.
+    pub mip_count: usize,
+    pub has_palette: bool,
+}
+
+/// Header information and decoded mip chain of a BLP file, as shown by the
+/// texture inspector. The levels missing from the file are generated.
+pub struct BlpInspection {
+    pub info: BlpInfo,
+    pub mips: Vec<RgbaImage>,
+}
+
+impl BlpInspection {
+    pub fn new(bytes: &[u8]) -> Result<Self, ImageLoaderError> {
+        let blp_image = blp::parser::load_blp_from_buf(bytes)?;
+        let mut mips = ImageLoader::decode_stored_mips(&blp_image)?;
+        let (compression, has_palette) = match &blp_image.content {
+            BlpContent::Jpeg(_) => ("JPEG", false),
+            BlpContent::Raw1(_) => ("Palettized", true),
+            BlpContent::Raw3(_) => ("Uncompressed BGRA", false),
+            BlpContent::Dxt1(_) => ("DXT1", false),
+            BlpContent::Dxt3(_) => ("DXT3", false),
+            BlpContent::Dxt5(_) => ("DXT5", false),
+        };
+        let info = BlpInfo {
+            compression,
+            alpha_bits: blp_image.header.alpha_bits(),
+            width: blp_image.header.width,
+            height: blp_image.header.height,
+            mip_count: mips.len(),
+            has_palette,
+        };
+        generate_mips(&mut mips, ColorSpace::Srgb);
+        Ok(Self { info, mips })
+    }
+}
+
+/// Number of levels of a full mip chain, down to 1×1.
+pub fn get_mip_count(width: u32, height: u32) -> u32 {
+    32 - width.max(height).max(1).leading_zeros()
+}
+
+/// Size of a mip level, as expected by the GPU.
+pub fn get_mip_size(width: u32, height: u32, level: u32) -> (u32, u32) {
+    ((width >> level).max(1), (height >> level).max(1))
+}
+
+/// Size in bytes of a block-compressed mip level. Levels smaller than a
+/// block still take a whole block.
+fn get_compressed_mip_size(width: u32, height: u32, level: u32, block_size: usize) -> usize {
+    let (mip_width, mip_height) = get_mip_size(width, height, level);
+    mip_width.div_ceil(4) as usize * mip_height.div_ceil(4) as usize * block_size
+}
+
+/// Appends the levels missing after the last one, each a downscale of the
+/// previous one. The texels are filtered in linear light, so averaging the
+/// sRGB colours does not darken the smaller levels; alpha is always linear.
+fn generate_mips(mips: &mut Vec<RgbaImage>, color_space: ColorSpace) {
+    let Some(last) = mips.last() else {
+        return;
+    };
+    // Kept in floating point down the chain to not accumulate rounding errors
+    let mut level = Rgba32FImage::from_fn(last.width(), last.height(), |x, y| {
+        let [r, g, b, a] = last.get_pixel(x, y).0;
+        let [r, g, b] = [r, g, b].map(|channel| color_space.decode_channel(channel));
+        ::image::Rgba([r, g, b, a as f32 / 255.0])
+    });
+    while level.width() > 1 || level.height() > 1 {
+        let width = (level.width() / 2).max(1);
+        let height = (level.height() / 2).max(1);
+        level = imageops::resize(&level, width, height, imageops::FilterType::Triangle);
+        let mip = RgbaImage::from_fn(width, height, |x, y| {
+            let [r, g, b, a] = level.get_pixel(x, y).0;
+            let [r, g, b] = [r, g, b].map(|channel| color_space.encode_channel(channel));
+            ::image::Rgba([r, g, b, ColorSpace::Linear.encode_channel(a)])
+        });
+        mips.push(mip);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_mip_chain() {
+        assert_eq!(get_mip_count(1, 1), 1);
+        assert_eq!(get_mip_count(256, 64), 9);
+        assert_eq!(get_mip_count(100, 3), 7);
+        assert_eq!(get_mip_size(100, 3, 6), (1, 1));
+
+        let mut mips = vec![RgbaImage::from_pixel(8, 2, ::image::Rgba([255, 0, 0, 255]))];
+        generate_mips(&mut mips, ColorSpace::Srgb);
+        let sizes: Vec<_> = mips.iter().map(|mip| mip.dimensions()).collect();
+        assert_eq!(sizes, [(8, 2), (4, 1), (2, 1), (1, 1)]);
+        assert_eq!(sizes.len() as u32, get_mip_count(8, 2));
+        assert_eq!(mips[3].get_pixel(0, 0).0, [255, 0, 0, 255]);
+
+        // Black and white average to half the light, not half the sRGB value
+        let checker = RgbaImage::from_fn(2, 1, |x, _| {
+            let value = if x == 0 { 0 } else { 255 };
+            ::image::Rgba([value, value, value, value])
+        });
+        let mut mips = vec![checker.clone()];
+        generate_mips(&mut mips, ColorSpace::Srgb);
+        assert_eq!(mips[1].get_pixel(0, 0).0, [188, 188, 188, 128]);
+        let mut mips = vec![checker];
+        generate_mips(&mut mips, ColorSpace::Linear);
+        assert_eq!(mips[1].get_pixel(0, 0).0, [128, 128, 128, 128]);
+    }
+
+    #[test]
+    fn test_color_space() {
+        let srgb = ColorSpace::Srgb;
+        assert_eq!(
+            srgb.apply(TextureFormat::Rgba8Unorm),
+            TextureFormat::Rgba8UnormSrgb
+        );
+        assert_eq!(
+            srgb.apply(TextureFormat::Bc3RgbaUnorm),
+            TextureFormat::Bc3RgbaUnormSrgb
+        );
+        assert_eq!(
+            ColorSpace::Linear.apply(TextureFormat::Bc1RgbaUnormSrgb),
+            TextureFormat::Bc1RgbaUnorm
+        );
+    }
+
+    #[test]
+    fn test_compressed_mip_size() {
+        // 64×32 DXT1: 16×8 blocks of 8 bytes
+        assert_eq!(get_compressed_mip_size(64, 32, 0, 8), 1024);
+        assert_eq!(get_compressed_mip_size(64, 32, 3, 8), 2 * 8);
+        // 2×1 and 1×1 levels take a whole block
+        assert_eq!(get_compressed_mip_size(64, 32, 5, 16), 16);
+        assert_eq!(get_compressed_mip_size(64, 32, 6, 16), 16);
     }
 }
@@ -103,10 +103,11 @@ pub fn sampler_from_model_texture_flags(
     } else {
         ImageAddressMode::ClampToEdge
     };
+    // Linear filtering between the texels and the mip levels
     let descriptor = ImageSamplerDescriptor {
         address_mode_u,
         address_mode_v,
-        ..Default::default()
+        ..ImageSamplerDescriptor::linear()
     };
     ImageSampler::Descriptor(descriptor)
 }
@@ -125,10 +126,11 @@ pub fn sampler_from_world_model_material_flags(
     } else {
         ImageAddressMode::Repeat
     };
+    // Linear filtering between the texels and the mip levels
     let descriptor = ImageSamplerDescriptor {
         address_mode_u,
         address_mode_v,
-        ..Default::default()
+        ..ImageSamplerDescriptor::linear()
     };
     ImageSampler::Descriptor(descriptor)
 }
@@ -9,6 +9,7 @@ pub mod image;
 pub mod material;
 pub mod mesh;
 pub mod model;
+pub mod raw;
 pub mod root_aabb;
 pub mod world_map;
 pub mod world_model;
@@ -19,12 +20,15 @@ use image::*;
 use material::*;
 use mesh::*;
 use model::*;
+use raw::*;
 use root_aabb::*;
 use world_map::*;
 use world_model::*;
 
 use bevy::prelude::*;
 
+use crate::settings::Settings;
+
 pub struct WorgenAssetPlugin;
 
 impl Plugin for WorgenAssetPlugin {
@@ -37,65 +41,77 @@ impl Plugin for WorgenAssetPlugin {
             .init_asset::<WorldModelAsset>()
             .init_asset::<WorldMapAsset>()
             .init_asset::<DataBaseAsset>()
-            .init_asset_loader::<ImageLoader>()
+            .init_asset::<RawAsset>()
             .init_asset_loader::<ModelAssetLoader>()
             .init_asset_loader::<WorldModelAssetLoader>()
             .init_asset_loader::<WorldMapAssetLoader>()
             .init_asset_loader::<DataBaseAssetLoader>()
+            .init_asset_loader::<RawAssetLoader>()
             .add_plugins(MaterialPlugin::<ExtTerrainMaterial>::default())
             .add_plugins(GeosetRuntimePlugin)
-            .add_systems(PreStartup, archive::FileArchiveMap::init);
+            .add_systems(PreStartup, archive::init_archive_registry);
+    }
+
+    fn finish(&self, app: &mut App) {
+        // The supported compressed formats are only known once the renderer
+        // is initialized
+        app.init_asset_loader::<ImageLoader>();
     }
 }
 
-#[cfg(test)]
-pub mod test {
+/// Builds an app loading assets without a window nor a GPU.
+/// More plugins can be added before calling `App::finish` and `App::cleanup`.
+pub fn headless_app(settings: Settings) -> App {
+    use bevy::*;
     use std::time::Duration;
 
-    use crate::settings::Settings;
-    use bevy::*;
+    let mut app = App::new();
+
+    app.insert_resource(settings);
+
+    app.add_plugins((
+        archive::ArchiveAssetReaderPlugin,
+        DefaultPlugins
+            .set(WindowPlugin {
+                primary_window: None,
+                // Don't automatically exit due to having no windows.
+                exit_condition: window::ExitCondition::DontExit,
+                ..default()
+            })
+            // WinitPlugin will panic in environments without a display server.
+            .disable::<winit::WinitPlugin>()
+            .set(render::RenderPlugin {
+                synchronous_pipeline_compilation: true,
+                render_creation: render::settings::RenderCreation::Automatic(
+                    render::settings::WgpuSettings {
+                        backends: None,
+                        ..default()
+                    },
+                ),
+                ..default()
+            })
+            .set(AssetPlugin {
+                meta_check: bevy::asset::AssetMetaCheck::Never,
+                ..default()
+            }),
+        // ScheduleRunnerPlugin provides an alternative to the default bevy_winit app runner, which
+        // manages the loop without creating a window.
+        app::ScheduleRunnerPlugin::run_loop(
+            // Run 60 times per second.
+            Duration::from_secs_f64(1.0 / 60.0),
+        ),
+        WorgenAssetPlugin,
+    ));
+
+    app
+}
 
+#[cfg(test)]
+pub mod test {
     use super::*;
 
     pub fn test_app() -> App {
-        Settings::init();
-        archive::FileArchiveMap::init();
-
-        let mut app = App::new();
-
-        app.add_plugins((
-            archive::ArchiveAssetReaderPlugin,
-            DefaultPlugins
-                .set(WindowPlugin {
-                    primary_window: None,
-                    // Don't automatically exit due to having no windows.
-                    exit_condition: window::ExitCondition::DontExit,
-                    ..default()
-                })
-                // WinitPlugin will panic in environments without a display server.
-                .disable::<winit::WinitPlugin>()
-                .set(render::RenderPlugin {
-                    synchronous_pipeline_compilation: true,
-                    render_creation: render::settings::RenderCreation::Automatic(
-                        render::settings::WgpuSettings {
-                            backends: None,
-                            ..default()
-                        },
-                    ),
-                    ..default()
-                })
-                .set(AssetPlugin {
-                    meta_check: bevy::asset::AssetMetaCheck::Never,
-                    ..default()
-                }),
-            // ScheduleRunnerPlugin provides an alternative to the default bevy_winit app runner, which
-            // manages the loop without creating a window.
-            app::ScheduleRunnerPlugin::run_loop(
-                // Run 60 times per second.
-                Duration::from_secs_f64(1.0 / 60.0),
-            ),
-            WorgenAssetPlugin,
-        ));
+        let mut app = headless_app(Settings::load().expect("Failed to load settings"));
 
         app.finish();
         app.cleanup();
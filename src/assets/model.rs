@@ -23,7 +23,6 @@ use std::path::PathBuf;
 use anyhow::Result;
 use bevy::asset::io::Reader;
 use bevy::asset::*;
-use bevy::image::ImageLoaderSettings;
 use bevy::mesh::*;
 use bevy::prelude::*;
 use bevy::render::render_resource::Face;
@@ -139,8 +138,22 @@ pub struct MeshData {
     geosets: Vec<Geoset>,
 }
 
-#[derive(Default)]
-pub struct ModelAssetLoader;
+pub struct ModelAssetLoader {
+    /// Image used in place of textures that can not be resolved.
+    fallback_image_path: String,
+}
+
+impl FromWorld for ModelAssetLoader {
+    fn from_world(world: &mut World) -> Self {
+        let fallback_image_path = world
+            .get_resource::<Settings>()
+            .map(|settings| settings.test_image_path.clone())
+            .unwrap_or_default();
+        Self {
+            fallback_image_path,
+        }
+    }
+}
 
 #[derive(Debug, Error)]
 pub enum ModelAssetLoaderError {
@@ -156,15 +169,17 @@ pub enum ModelAssetLoaderError {
 
 impl ModelAssetLoader {
     pub async fn load_path(
+        &self,
         model_path: &str,
         load_context: &mut LoadContext<'_>,
     ) -> Result<ModelAsset, ModelAssetLoaderError> {
-        let model_asset_path = format!("archive://{}", model_path);
+        let model_asset_path = archive::get_asset_path(load_context, model_path);
         let bytes = load_context.read_asset_bytes(&model_asset_path).await?;
-        Self::load_model(model_path, bytes, load_context).await
+        self.load_model(model_path, bytes, load_context).await
     }
 
     pub async fn load_model(
+        &self,
         model_path: &str,
         bytes: Vec<u8>,
         load_context: &mut LoadContext<'_>,
@@ -172,7 +187,7 @@ impl ModelAssetLoader {
         let mut cursor = io::Cursor::new(&bytes);
         let model = m2::M2Model::parse(&mut cursor)?;
 
-        let images = Self::load_images(&model, load_context);
+        let images = self.load_images(&model, load_context);
         let mut data = MeshData::default();
         Self::load_meshes(&model, &bytes, &images, &mut data)?;
 
@@ -224,16 +239,21 @@ impl ModelAssetLoader {
         })
     }
 
-    fn load_images(model: &m2::M2Model, load_context: &mut LoadContext<'_>) -> Vec<Handle<Image>> {
+    fn load_images(
+        &self,
+        model: &m2::M2Model,
+        load_context: &mut LoadContext<'_>,
+    ) -> Vec<Handle<Image>> {
         let mut handles = Vec::new();
         for texture in &model.textures {
-            let image_path = Self::get_image_asset_path(texture);
+            let image_path = self.get_image_asset_path(texture, load_context);
             let sampler = sampler_from_model_texture_flags(texture.flags);
             handles.push(
                 load_context
                     .loader()
-                    .with_settings(move |settings: &mut ImageLoaderSettings| {
+                    .with_settings(move |settings: &mut ImageSettings| {
                         settings.sampler = sampler.clone();
+                        settings.color_space = ColorSpace::Srgb;
                     })
                     .load(image_path),
             );
@@ -241,21 +261,25 @@ impl ModelAssetLoader {
         handles
     }
 
-    fn get_image_path(texture: &m2::chunks::texture::M2Texture) -> String {
+    fn get_image_path(&self, texture: &m2::chunks::texture::M2Texture) -> String {
         if texture.texture_type != m2::chunks::M2TextureType::Hardcoded {
             // Ignore non-hardcoded textures for now.
             warn!("Non-hardcoded texture found, using test image instead.");
-            return Settings::get().test_image_path.clone();
+            return self.fallback_image_path.clone();
         }
         let filename = texture.filename.string.to_string_lossy();
         if filename.is_empty() {
-            return Settings::get().test_image_path.clone();
+            return self.fallback_image_path.clone();
         }
         filename.to_string()
     }
 
-    fn get_image_asset_path(texture: &m2::chunks::texture::M2Texture) -> String {
-        format!("archive://{}", Self::get_image_path(texture))
+    fn get_image_asset_path(
+        &self,
+        texture: &m2::chunks::texture::M2Texture,
+        load_context: &LoadContext<'_>,
+    ) -> String {
+        archive::get_asset_path(load_context, self.get_image_path(texture))
     }
 
     fn load_meshes(
@@ -388,7 +412,7 @@ impl AssetLoader for ModelAssetLoader {
         let mut bytes = Vec::new();
         reader.read_to_end(&mut bytes).await?;
         let model_path = load_context.path().to_string_lossy().into_owned();
-        Self::load_model(&model_path, bytes, load_context).await
+        self.load_model(&model_path, bytes, load_context).await
     }
 
     fn extensions(&self) -> &[&str] {
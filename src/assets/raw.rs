@@ -0,0 +1,63 @@
+// Copyright © 2025
+// Author: Nocthir <nocthir@proton.me>
+// SPDX-License-Identifier: MIT or Apache-2.0
+
+use std::io;
+
+use bevy::{
+    asset::{io::Reader, *},
+    prelude::*,
+};
+use thiserror::Error;
+
+use crate::data::file::FILE_CLASSES;
+
+/// Bytes of a file without a dedicated loader.
+#[derive(Asset, Debug, TypePath)]
+pub struct RawAsset {
+    pub bytes: Vec<u8>,
+}
+
+/// Loads the files of every category without a dedicated loader.
+pub struct RawAssetLoader {
+    extensions: Vec<&'static str>,
+}
+
+impl Default for RawAssetLoader {
+    fn default() -> Self {
+        let extensions = FILE_CLASSES
+            .iter()
+            .filter(|class| !class.has_loader)
+            .flat_map(|class| class.extensions)
+            .copied()
+            .collect();
+        Self { extensions }
+    }
+}
+
+impl AssetLoader for RawAssetLoader {
+    type Asset = RawAsset;
+    type Settings = ();
+    type Error = RawAssetLoaderError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &Self::Settings,
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        Ok(RawAsset { bytes })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &self.extensions
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum RawAssetLoaderError {
+    #[error("IO error: {0}")]
+    Io(#[from] io::Error),
+}
@@ -197,8 +197,13 @@ impl WorldMapAssetLoader {
         load_context: &mut LoadContext<'_>,
     ) -> Result<Vec<Handle<Image>>> {
         let mut images = Vec::new();
-        for image_path in Self::get_image_asset_paths(world_map) {
-            let image = load_context.load(image_path);
+        for image_path in Self::get_image_asset_paths(world_map, load_context) {
+            let image = load_context
+                .loader()
+                .with_settings(|settings: &mut ImageSettings| {
+                    settings.color_space = ColorSpace::Srgb;
+                })
+                .load(image_path);
             images.push(image);
         }
         Ok(images)
@@ -214,10 +219,10 @@ impl WorldMapAssetLoader {
         paths
     }
 
-    fn get_image_asset_paths(world_map: &adt::Adt) -> Vec<String> {
+    fn get_image_asset_paths(world_map: &adt::Adt, load_context: &LoadContext<'_>) -> Vec<String> {
         Self::get_image_paths(world_map)
             .iter()
-            .map(|p| format!("archive://{}", p))
+            .map(|p| archive::get_asset_path(load_context, p))
             .collect()
     }
 
@@ -460,6 +465,7 @@ impl WorldMapAssetLoader {
             image_size,
             TextureDimension::D2,
             combined_alpha.as_slice(),
+            // Alpha maps are masks, sampled as stored
             TextureFormat::Rgba8Unorm,
             RenderAssetUsages::RENDER_WORLD,
         );
@@ -474,20 +480,20 @@ impl WorldMapAssetLoader {
         load_context: &mut LoadContext<'_>,
     ) -> Vec<Handle<ModelAsset>> {
         let mut models = Vec::new();
-        for model_path in Self::get_model_asset_paths(world_map) {
+        for model_path in Self::get_model_asset_paths(world_map, load_context) {
             models.push(load_context.load(model_path));
         }
         models
     }
 
-    fn get_model_asset_paths(world_map: &adt::Adt) -> Vec<String> {
+    fn get_model_asset_paths(world_map: &adt::Adt, load_context: &LoadContext<'_>) -> Vec<String> {
         let mut models = Vec::new();
         if let Some(mmdx) = &world_map.mmdx {
             models.extend(
                 mmdx.filenames
                     .iter()
                     .filter(|f| f.ends_with(".m2"))
-                    .map(|f| format!("archive://{}", f)),
+                    .map(|f| archive::get_asset_path(load_context, f)),
             );
         }
         models
@@ -498,7 +504,7 @@ impl WorldMapAssetLoader {
         world_map: &adt::Adt,
         load_context: &mut LoadContext<'_>,
     ) {
-        let model_asset_paths = Self::get_model_asset_paths(world_map);
+        let model_asset_paths = Self::get_model_asset_paths(world_map, load_context);
 
         if let Some(mddf) = &world_map.mddf {
             for placement in &mddf.doodads {
@@ -529,17 +535,20 @@ impl WorldMapAssetLoader {
         load_context: &mut LoadContext<'_>,
     ) -> Vec<Handle<WorldModelAsset>> {
         let mut world_models = Vec::new();
-        for world_model_path in Self::get_world_model_asset_paths(world_map) {
+        for world_model_path in Self::get_world_model_asset_paths(world_map, load_context) {
             world_models.push(load_context.load(&world_model_path));
         }
         world_models
     }
 
-    fn get_world_model_asset_paths(world_map: &adt::Adt) -> Vec<String> {
+    fn get_world_model_asset_paths(
+        world_map: &adt::Adt,
+        load_context: &LoadContext<'_>,
+    ) -> Vec<String> {
         let mut paths = Vec::new();
         if let Some(mwmo) = &world_map.mwmo {
             for filename in &mwmo.filenames {
-                paths.push(format!("archive://{}", filename));
+                paths.push(archive::get_asset_path(load_context, filename));
             }
         }
         paths
@@ -550,7 +559,7 @@ impl WorldMapAssetLoader {
         world_map: &adt::Adt,
         load_context: &mut LoadContext<'_>,
     ) {
-        let paths = Self::get_world_model_asset_paths(world_map);
+        let paths = Self::get_world_model_asset_paths(world_map, load_context);
 
         if let Some(modf) = &world_map.modf
             && let Some(mwid) = &world_map.mwid
@@ -8,7 +8,6 @@ use std::path::{Path, PathBuf};
 use anyhow::{Result, anyhow};
 use bevy::asset::io::Reader;
 use bevy::asset::*;
-use bevy::image::ImageLoaderSettings;
 use bevy::mesh::*;
 use bevy::prelude::*;
 use bevy::render::render_resource::Face;
@@ -135,7 +134,7 @@ impl WorldModelAssetLoader {
         model_path: &str,
         load_context: &mut LoadContext<'_>,
     ) -> Result<WorldModelAsset, WorldModelAssetLoaderError> {
-        let model_asset_path = format!("archive://{}", model_path);
+        let model_asset_path = archive::get_asset_path(load_context, model_path);
         let bytes = load_context.read_asset_bytes(&model_asset_path).await?;
         Self::load_model(model_path, bytes, load_context).await
     }
@@ -220,7 +219,7 @@ impl WorldModelAssetLoader {
         group_index: u32,
         load_context: &mut LoadContext<'_>,
     ) -> Result<wmo::group_parser::WmoGroup> {
-        let group_filename = Self::get_group_filename(file_path, group_index);
+        let group_filename = Self::get_group_filename(file_path, group_index, load_context);
         let bytes = load_context.read_asset_bytes(&group_filename).await?;
         let mut reader = io::Cursor::new(&bytes);
         let wmo::ParsedWmo::Group(group) = wmo::parse_wmo(&mut reader)? else {
@@ -229,9 +228,14 @@ impl WorldModelAssetLoader {
         Ok(group)
     }
 
-    fn get_group_filename<P: AsRef<Path>>(wmo_path: P, group_index: u32) -> String {
+    fn get_group_filename<P: AsRef<Path>>(
+        wmo_path: P,
+        group_index: u32,
+        load_context: &LoadContext<'_>,
+    ) -> String {
         let base_path = wmo_path.as_ref().with_extension("");
-        format!("archive://{}_{:03}.wmo", base_path.display(), group_index)
+        let group_path = format!("{}_{:03}.wmo", base_path.display(), group_index);
+        archive::get_asset_path(load_context, group_path)
     }
 
     async fn load_images(
@@ -240,7 +244,7 @@ impl WorldModelAssetLoader {
     ) -> Result<Vec<Handle<Image>>> {
         let mut images = vec![Handle::default(); root.textures.len()];
 
-        let image_paths = Self::get_image_asset_paths(root);
+        let image_paths = Self::get_image_asset_paths(root, load_context);
 
         // Set image samplers
         for material in &root.materials {
@@ -254,8 +258,9 @@ impl WorldModelAssetLoader {
             let image_path = &image_paths[texture_index as usize];
             images[texture_index as usize] = load_context
                 .loader()
-                .with_settings(move |settings: &mut ImageLoaderSettings| {
+                .with_settings(move |settings: &mut ImageSettings| {
                     settings.sampler = sampler.clone();
+                    settings.color_space = ColorSpace::Srgb;
                 })
                 .load(image_path);
         }
@@ -267,10 +272,13 @@ impl WorldModelAssetLoader {
         &root.textures
     }
 
-    fn get_image_asset_paths(root: &wmo::root_parser::WmoRoot) -> Vec<String> {
+    fn get_image_asset_paths(
+        root: &wmo::root_parser::WmoRoot,
+        load_context: &LoadContext<'_>,
+    ) -> Vec<String> {
         Self::get_image_paths(root)
             .iter()
-            .map(|texture_path| format!("archive://{}", texture_path))
+            .map(|texture_path| archive::get_asset_path(load_context, texture_path))
             .collect()
     }
 
@@ -456,7 +464,7 @@ pub fn is_world_model_root_path(file_path: &str) -> bool {
     !is_world_model_group_path(file_path)
 }
 
-fn is_world_model_group_path(file_path: &str) -> bool {
+pub fn is_world_model_group_path(file_path: &str) -> bool {
     if !is_world_model_extension(file_path) {
         return false;
     }
@@ -0,0 +1,213 @@
+// Copyright © 2025
+// Author: Nocthir <nocthir@proton.me>
+// SPDX-License-Identifier: MIT or Apache-2.0
+
+//! Headless extraction of files from the game archives.
+//!
+//! Usage: `worgen-extract [--output <dir>] [--lowercase] [--settings <file>]
+//! [--cache <file>] <path or pattern>...`
+//!
+//! Paths are resolved like the `archive://` asset source does, including patches
+//! and the overlay folder configured in the settings file (`assets/settings.json`
+//! by default). The archive index cache is stored next to the settings file,
+//! unless given. Patterns accept `?`, `*` and `**` (see `glob_match`). Extracted
+//! files keep their directory layout under the output folder; archive paths that
+//! would escape it, e.g. with `..` parts, are rejected.
+
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::process::ExitCode;
+
+use bevy::prelude::Result;
+use worgen_rs::{
+    assets::archive::*,
+    settings::{SETTINGS_PATH, Settings},
+};
+
+const USAGE: &str = "Usage: worgen-extract [--output <dir>] [--lowercase] [--settings <file>] \
+                     [--cache <file>] <path or pattern>...";
+const CACHE_FILE_NAME: &str = "archive_cache.json";
+
+#[derive(Default)]
+struct Options {
+    output_path: PathBuf,
+    lowercase: bool,
+    settings_path: PathBuf,
+    cache_path: Option<PathBuf>,
+    help: bool,
+    patterns: Vec<String>,
+}
+
+impl Options {
+    fn parse() -> Result<Self> {
+        let mut options = Self {
+            output_path: PathBuf::from("."),
+            settings_path: PathBuf::from(SETTINGS_PATH),
+            ..Default::default()
+        };
+        let mut args = std::env::args().skip(1);
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "-o" | "--output" => {
+                    let output_path = args.next().ok_or("Missing output folder")?;
+                    options.output_path = output_path.into();
+                }
+                "-l" | "--lowercase" => options.lowercase = true,
+                "-s" | "--settings" => {
+                    let settings_path = args.next().ok_or("Missing settings file")?;
+                    options.settings_path = settings_path.into();
+                }
+                "-c" | "--cache" => {
+                    let cache_path = args.next().ok_or("Missing cache file")?;
+                    options.cache_path = Some(cache_path.into());
+                }
+                "-h" | "--help" => options.help = true,
+                _ if arg.starts_with('-') => return Err(format!("Unknown option `{arg}`").into()),
+                _ => options.patterns.push(arg),
+            }
+        }
+        if options.patterns.is_empty() && !options.help {
+            return Err(USAGE.into());
+        }
+        Ok(options)
+    }
+
+    fn get_cache_path(&self) -> PathBuf {
+        self.cache_path.clone().unwrap_or_else(|| {
+            let folder = self.settings_path.parent().unwrap_or(Path::new(""));
+            folder.join(CACHE_FILE_NAME)
+        })
+    }
+
+    /// Returns where to write a file, keeping its directory layout. Archive
+    /// paths with parts that are not plain names, such as `..` or a drive, are
+    /// rejected so a crafted archive cannot write outside the output folder.
+    fn get_output_path(&self, file_path: &str) -> Result<PathBuf> {
+        let file_path = if self.lowercase {
+            file_path.to_lowercase()
+        } else {
+            file_path.to_string()
+        };
+        let mut ret = self.output_path.clone();
+        for part in file_path.split(['\\', '/']).filter(|part| !part.is_empty()) {
+            let mut components = Path::new(part).components();
+            match (components.next(), components.next()) {
+                (Some(Component::Normal(name)), None) => ret.push(name),
+                _ => return Err(format!("Unsafe path part `{part}`").into()),
+            }
+        }
+        if !ret.starts_with(&self.output_path) {
+            return Err("Path outside of the output folder".into());
+        }
+        Ok(ret)
+    }
+}
+
+fn main() -> ExitCode {
+    let options = match Options::parse() {
+        Ok(options) => options,
+        Err(err) => {
+            eprintln!("{err}");
+            return ExitCode::FAILURE;
+        }
+    };
+    if options.help {
+        println!("{USAGE}");
+        return ExitCode::SUCCESS;
+    }
+    let settings = match Settings::load_from(&options.settings_path) {
+        Ok(settings) => settings,
+        Err(err) => {
+            eprintln!("Failed to load settings: {err}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let registry = ArchiveRegistry::default();
+    registry.set(FileArchiveMap::with_cache(
+        &settings,
+        options.get_cache_path(),
+    ));
+    let file_archive_map = registry.get();
+    for error in &file_archive_map.errors {
+        eprintln!("Skipped {}: {}", error.path.display(), error.message);
+    }
+
+    let reader = ArchiveAssetReader::new(registry);
+    let mut extracted_count = 0;
+    let mut missing = Vec::new();
+    let mut failed = Vec::new();
+
+    for pattern in &options.patterns {
+        // A plain path matches itself, and resolves to its original casing
+        let file_paths = file_archive_map.find_files(pattern);
+        if file_paths.is_empty() {
+            missing.push(pattern);
+        }
+
+        for file_path in file_paths {
+            let output_path = match options.get_output_path(&file_path) {
+                Ok(output_path) => output_path,
+                Err(err) => {
+                    failed.push((file_path, err));
+                    continue;
+                }
+            };
+            match extract_file(&reader, &file_path, &output_path) {
+                Ok(()) => {
+                    println!("{}", output_path.display());
+                    extracted_count += 1;
+                }
+                Err(err) => failed.push((file_path, err)),
+            }
+        }
+    }
+
+    for pattern in &missing {
+        eprintln!("Not found: {pattern}");
+    }
+    for (file_path, err) in &failed {
+        eprintln!("Failed to extract {file_path}: {err}");
+    }
+    eprintln!(
+        "Extracted {extracted_count} files, {} not found, {} failed",
+        missing.len(),
+        failed.len()
+    );
+
+    if missing.is_empty() && failed.is_empty() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
+
+fn extract_file(reader: &ArchiveAssetReader, file_path: &str, output_path: &Path) -> Result<()> {
+    let bytes = reader.read_file(file_path)?;
+    if let Some(parent) = output_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(output_path, bytes)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_output_path() {
+        let options = Options {
+            output_path: PathBuf::from("out"),
+            lowercase: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            options.get_output_path("World\\Maps\\Azeroth.wdt").unwrap(),
+            Path::new("out/world/maps/azeroth.wdt")
+        );
+        assert!(options.get_output_path("..\\..\\x").is_err());
+        assert!(options.get_output_path("world\\.\\x").is_err());
+        assert!(options.get_output_path("world/../../x").is_err());
+    }
+}
@@ -0,0 +1,128 @@
+// Copyright © 2025
+// Author: Nocthir <nocthir@proton.me>
+// SPDX-License-Identifier: MIT or Apache-2.0
+
+//! Headless validation of the asset loaders over the archive files.
+//!
+//! Usage: `worgen-validate [--output <report.json>] [--baseline <report.json>] [pattern]...`
+//!
+//! Loads every file matching the patterns (all files by default) through the
+//! asset loaders and writes a JSON report of the results. With a baseline
+//! report, files failing now but not in the baseline are reported and make the
+//! command exit with a failure code.
+
+use std::fs;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use bevy::prelude::*;
+use worgen_rs::{
+    assets,
+    data::validation::{Validation, ValidationPlugin, ValidationReport},
+    settings::Settings,
+};
+
+const USAGE: &str =
+    "Usage: worgen-validate [--output <report.json>] [--baseline <report.json>] [pattern]...";
+
+struct Options {
+    output_path: PathBuf,
+    baseline_path: Option<PathBuf>,
+    help: bool,
+    patterns: Vec<String>,
+}
+
+impl Options {
+    fn parse() -> Result<Self> {
+        let mut options = Self {
+            output_path: PathBuf::from("validation_report.json"),
+            baseline_path: None,
+            help: false,
+            patterns: Vec::new(),
+        };
+        let mut args = std::env::args().skip(1);
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "-o" | "--output" => {
+                    let output_path = args.next().ok_or("Missing output file")?;
+                    options.output_path = output_path.into();
+                }
+                "-b" | "--baseline" => {
+                    let baseline_path = args.next().ok_or("Missing baseline file")?;
+                    options.baseline_path = Some(baseline_path.into());
+                }
+                "-h" | "--help" => options.help = true,
+                _ if arg.starts_with('-') => return Err(format!("Unknown option `{arg}`").into()),
+                _ => options.patterns.push(arg),
+            }
+        }
+        Ok(options)
+    }
+}
+
+fn main() -> ExitCode {
+    match run() {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        Err(err) => {
+            eprintln!("{err}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Returns whether the validation found no regression.
+fn run() -> Result<bool> {
+    let options = Options::parse()?;
+    if options.help {
+        println!("{USAGE}");
+        return Ok(true);
+    }
+    let baseline = match &options.baseline_path {
+        Some(baseline_path) => {
+            let file = fs::read(baseline_path)?;
+            Some(serde_json::from_slice::<ValidationReport>(&file)?)
+        }
+        None => None,
+    };
+
+    let settings = Settings::load().map_err(|err| format!("Failed to load settings: {err}"))?;
+    let mut app = assets::headless_app(settings);
+    app.add_plugins(ValidationPlugin {
+        patterns: options.patterns,
+    });
+    app.finish();
+    app.cleanup();
+
+    while !app.world().resource::<Validation>().is_done() {
+        app.update();
+    }
+
+    let report = &app.world().resource::<Validation>().report;
+    fs::write(&options.output_path, serde_json::to_vec_pretty(report)?)?;
+    println!(
+        "Validated {} files in {:.1} s: {} succeeded, {} failed",
+        report.file_count,
+        report.duration_ms / 1000.0,
+        report.success_count,
+        report.failure_count
+    );
+    for (error_kind, count) in &report.errors {
+        println!("  {error_kind}: {count}");
+    }
+    println!("Report written to {}", options.output_path.display());
+
+    let Some(baseline) = baseline else {
+        return Ok(true);
+    };
+    let regressions = report.get_regressions(&baseline);
+    for file in &regressions {
+        eprintln!(
+            "Regression: {} ({})",
+            file.path,
+            file.error.as_deref().unwrap_or_default()
+        );
+    }
+    eprintln!("{} regressions", regressions.len());
+    Ok(regressions.is_empty())
+}
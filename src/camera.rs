@@ -4,17 +4,16 @@
 
 use std::f32::consts::{FRAC_PI_2, PI, TAU};
 
-use bevy::camera::primitives::Aabb;
+use bevy::camera::{primitives::Aabb, visibility::RenderLayers};
 use bevy::input::mouse::*;
 use bevy::prelude::*;
 use bevy::render::view::Hdr;
 use bevy::*;
 use bevy_egui::EguiContexts;
 
-use crate::assets::model::Model;
 use crate::assets::root_aabb::RootAabb;
-use crate::assets::world_map::WorldMap;
-use crate::assets::world_model::WorldModel;
+use crate::comparison;
+use crate::data::SceneFile;
 
 /// Bundle to spawn our custom camera easily
 /// https://bevy-cheatbook.github.io/cookbook/pan-orbit-camera.html
@@ -117,18 +116,17 @@ pub struct PanOrbitCameraPlugin;
 
 impl Plugin for PanOrbitCameraPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Startup, setup_camera)
-            .add_systems(PreUpdate, on_world_map_loaded)
-            .add_systems(
-                PreUpdate,
-                (on_world_model_loaded, on_model_loaded).run_if(no_world_map),
-            )
-            .add_systems(Update, pan_orbit_camera);
+        app.add_message::<FocusRequested>()
+            .add_systems(Startup, setup_camera)
+            .add_systems(PreUpdate, on_scene_file_loaded)
+            .add_systems(Update, (on_focus_requested, pan_orbit_camera).chain());
     }
 }
 
-fn no_world_map(world_maps: Query<(), With<WorldMap>>) -> bool {
-    world_maps.is_empty()
+/// Requests the camera to focus on a scene file entity.
+#[derive(Message)]
+pub struct FocusRequested {
+    pub entity: Entity,
 }
 
 pub fn setup_camera(mut commands: Commands) {
@@ -147,6 +145,8 @@ pub fn setup_camera(mut commands: Commands) {
         },
         Transform::from_xyz(1.0, 4.0, 1.0).looking_at(Vec3::ZERO, Vec3::Y),
         cascade_shadow_config,
+        // Also lights the right file of a comparison
+        RenderLayers::from_layers(&[0, comparison::RIGHT_LAYER]),
     ));
     commands.insert_resource(AmbientLight {
         color: Color::WHITE,
@@ -340,34 +340,54 @@ fn pan_orbit_camera(
     }
 }
 
-fn on_model_loaded(
-    q_current: Query<&RootAabb, (Added<RootAabb>, With<Model>)>,
-    mut q_camera: Query<(&mut PanOrbitState, &mut Transform)>,
+type CameraQuery<'w, 's> =
+    Query<'w, 's, (&'static mut PanOrbitState, &'static mut Transform), Without<SceneFile>>;
+
+/// Focuses the files added to the scene once loaded. The scene roots of the
+/// models embedded in world maps and world models are not direct children of
+/// a scene file, so they are left out.
+fn on_scene_file_loaded(
+    q_roots: Query<(&RootAabb, &ChildOf), Added<RootAabb>>,
+    q_files: Query<&Transform, With<SceneFile>>,
+    mut q_camera: CameraQuery,
 ) {
-    if let Ok(root_aabb) = q_current.single() {
-        focus_camera(&root_aabb.aabb, &mut q_camera);
+    for (root_aabb, child_of) in &q_roots {
+        if let Ok(transform) = q_files.get(child_of.parent()) {
+            focus_camera(&get_scene_aabb(root_aabb, transform), &mut q_camera);
+        }
     }
 }
 
-fn on_world_model_loaded(
-    q_current: Query<&RootAabb, (Added<RootAabb>, With<WorldModel>)>,
-    mut q_camera: Query<(&mut PanOrbitState, &mut Transform)>,
+fn on_focus_requested(
+    mut event_reader: MessageReader<FocusRequested>,
+    q_roots: Query<(&RootAabb, &ChildOf)>,
+    q_files: Query<&Transform, With<SceneFile>>,
+    mut q_camera: CameraQuery,
 ) {
-    if let Ok(root_aabb) = q_current.single() {
-        focus_camera(&root_aabb.aabb, &mut q_camera);
+    // Ignore all but the last event
+    let Some(event) = event_reader.read().last() else {
+        return;
+    };
+    let Ok(transform) = q_files.get(event.entity) else {
+        return;
+    };
+    if let Some((root_aabb, _)) = q_roots
+        .iter()
+        .find(|(_, child_of)| child_of.parent() == event.entity)
+    {
+        focus_camera(&get_scene_aabb(root_aabb, transform), &mut q_camera);
     }
 }
 
-fn on_world_map_loaded(
-    q_current: Query<&RootAabb, (Added<RootAabb>, With<WorldMap>)>,
-    mut q_camera: Query<(&mut PanOrbitState, &mut Transform)>,
-) {
-    if let Ok(root_aabb) = q_current.single() {
-        focus_camera(&root_aabb.aabb, &mut q_camera);
-    }
+/// Returns the bounding box of a scene root in the scene, moved by the
+/// transform of its scene file.
+fn get_scene_aabb(root_aabb: &RootAabb, transform: &Transform) -> Aabb {
+    let mut root_aabb = *root_aabb;
+    root_aabb.transform(transform);
+    root_aabb.aabb
 }
 
-fn focus_camera(aabb: &Aabb, q_camera: &mut Query<(&mut PanOrbitState, &mut Transform)>) {
+fn focus_camera(aabb: &Aabb, q_camera: &mut CameraQuery) {
     let center = aabb.center;
     let mut radius = aabb.half_extents.length();
     // Clamp to a minimal reasonable radius
@@ -375,7 +395,7 @@ fn focus_camera(aabb: &Aabb, q_camera: &mut Query<(&mut PanOrbitState, &mut Tran
     let comfort = 2.5_f32;
     radius = (radius * comfort).max(min_radius);
 
-    for (mut state, mut transform) in q_camera {
+    for (mut state, mut transform) in q_camera.iter_mut() {
         state.center = center.into();
         state.radius = radius;
         // Preserve current yaw/pitch encoded in transform.rotation/state; recompute position only
@@ -0,0 +1,405 @@
+// Copyright © 2025
+// Author: Nocthir <nocthir@proton.me>
+// SPDX-License-Identifier: MIT or Apache-2.0
+
+//! Side by side comparison of two scene files.
+//!
+//! The main camera renders the left file and a second camera, following the
+//! `PanOrbitState` of the main camera, renders the right file in the right
+//! half of the viewport. Each file is kept to its camera with render layers,
+//! which are applied to every entity of its scene.
+//!
+//! Files are compared within the current game installation, or with the same
+//! file read from a comparison game installation through the `comparison://`
+//! asset source. The comparison installation is read without the overlay
+//! folder, so comparing a file with the current installation also shows what
+//! the overlay changes.
+
+use bevy::camera::visibility::RenderLayers;
+use bevy::prelude::*;
+use bevy::tasks::{self, Task};
+
+use crate::assets::{
+    archive::{ComparisonRegistry, FileArchiveMap},
+    material::TerrainMaterial,
+    model::Model,
+    world_model::WorldModel,
+};
+use crate::camera::{PanOrbitCameraBundle, PanOrbitSettings, PanOrbitState};
+use crate::data::{self, CurrentFile, SceneFile, file::FileCategory};
+use crate::settings::Settings;
+use crate::ui::{ComparisonFileSelected, ComparisonGamePathSelected};
+
+/// Render layer of the right file.
+pub const RIGHT_LAYER: usize = 1;
+/// Render layer of the scene files not compared, which no camera renders.
+const HIDDEN_LAYER: usize = 2;
+
+pub struct ComparisonPlugin;
+
+impl Plugin for ComparisonPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Comparison>()
+            .init_resource::<ComparisonStats>()
+            .add_systems(Startup, init_comparison_game)
+            .add_systems(
+                Update,
+                (
+                    switch_comparison_game_path,
+                    check_comparison_indexing.run_if(resource_exists::<ComparisonIndexingTask>),
+                    load_comparison_file,
+                )
+                    .chain(),
+            )
+            .add_systems(
+                PostUpdate,
+                (
+                    update_comparison,
+                    sync_comparison_camera,
+                    apply_render_layers.run_if(|comparison: Res<Comparison>| {
+                        comparison.enabled || comparison.is_changed()
+                    }),
+                    invalidate_comparison_stats,
+                    update_comparison_stats.run_if(|stats: Res<ComparisonStats>| stats.outdated),
+                )
+                    .chain()
+                    .before(TransformSystems::Propagate),
+            );
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct Comparison {
+    pub enabled: bool,
+    /// Scene file rendered by the main camera, on the left.
+    pub left: Option<Entity>,
+    /// Scene file rendered by the comparison camera, on the right.
+    pub right: Option<Entity>,
+}
+
+impl Comparison {
+    /// Returns the render layers of a scene file, `None` for the default layer.
+    fn get_render_layers(&self, entity: Entity) -> Option<RenderLayers> {
+        if !self.enabled || self.left == Some(entity) {
+            None
+        } else if self.right == Some(entity) {
+            Some(RenderLayers::layer(RIGHT_LAYER))
+        } else {
+            Some(RenderLayers::layer(HIDDEN_LAYER))
+        }
+    }
+}
+
+/// Statistics of the compared files, computed again when the comparison or
+/// the scene changes.
+#[derive(Resource, Default)]
+pub struct ComparisonStats {
+    pub left: SceneStats,
+    pub right: SceneStats,
+    outdated: bool,
+}
+
+/// Marks the camera rendering the right file.
+#[derive(Component)]
+pub struct ComparisonCamera;
+
+/// Indexing of the archives of the comparison game installation.
+#[derive(Resource)]
+pub struct ComparisonIndexingTask {
+    task: Task<FileArchiveMap>,
+}
+
+impl ComparisonIndexingTask {
+    fn new(settings: &Settings, game_path: String) -> Self {
+        let mut settings = data::get_game_settings(settings, game_path);
+        settings.overlay_path = None;
+        let task = tasks::IoTaskPool::get().spawn(async move { FileArchiveMap::new(&settings) });
+        Self { task }
+    }
+}
+
+/// Starts indexing the comparison game installation of the settings, if any.
+fn init_comparison_game(mut commands: Commands, settings: Res<Settings>) {
+    if let Some(game_path) = &settings.comparison_game_path {
+        commands.insert_resource(ComparisonIndexingTask::new(&settings, game_path.clone()));
+    }
+}
+
+/// Switches to another comparison game installation: removes the files of
+/// the previous one from the scene, and starts indexing the archives.
+fn switch_comparison_game_path(
+    mut event_reader: MessageReader<ComparisonGamePathSelected>,
+    q_files: Query<(Entity, &SceneFile)>,
+    mut commands: Commands,
+    mut settings: ResMut<Settings>,
+) {
+    // Ignore all but the last event
+    let Some(event) = event_reader.read().last() else {
+        return;
+    };
+
+    for (entity, _) in q_files.iter().filter(|(_, file)| file.comparison) {
+        commands.entity(entity).despawn();
+    }
+
+    settings.comparison_game_path = Some(event.game_path.clone());
+    if let Err(err) = settings.save() {
+        warn!("Failed to save settings: {err}");
+    }
+    // Replacing a previous task drops it
+    commands.insert_resource(ComparisonIndexingTask::new(
+        &settings,
+        event.game_path.clone(),
+    ));
+}
+
+/// Switches the comparison registry to the comparison game installation once
+/// indexed.
+fn check_comparison_indexing(
+    mut commands: Commands,
+    mut indexing_task: ResMut<ComparisonIndexingTask>,
+    registry: Res<ComparisonRegistry>,
+) {
+    let Some(file_archive_map) = tasks::block_on(tasks::poll_once(&mut indexing_task.task)) else {
+        return;
+    };
+    registry.0.set(file_archive_map);
+    commands.remove_resource::<ComparisonIndexingTask>();
+}
+
+/// Loads a file from the comparison game installation in place of the
+/// previous one, and shows it on the right.
+fn load_comparison_file(
+    mut event_reader: MessageReader<ComparisonFileSelected>,
+    q_files: Query<(Entity, &SceneFile)>,
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut comparison: ResMut<Comparison>,
+) {
+    // Ignore all but the last event
+    let Some(event) = event_reader.read().last() else {
+        return;
+    };
+    let has_scene_root = FileCategory::from_path(&event.file_path)
+        .is_some_and(|category| category.class().has_scene_root);
+    if !has_scene_root {
+        warn!("Cannot compare {}, it has no scene", event.file_path);
+        return;
+    }
+
+    for (entity, _) in q_files.iter().filter(|(_, file)| file.comparison) {
+        commands.entity(entity).despawn();
+    }
+
+    let scene_file = SceneFile::from_comparison(event.file_path.clone());
+    let handle = asset_server.load(format!("{}#Root", scene_file.get_asset_path()));
+    let entity = commands.spawn((scene_file, SceneRoot(handle))).id();
+    comparison.enabled = true;
+    comparison.right = Some(entity);
+}
+
+/// Spawns or despawns the comparison camera, and fills the compared files
+/// with the current file and the most recent other scene file.
+fn update_comparison(
+    mut commands: Commands,
+    mut comparison: ResMut<Comparison>,
+    q_files: Query<(Entity, Has<CurrentFile>), With<SceneFile>>,
+    q_comparison_camera: Query<Entity, With<ComparisonCamera>>,
+) {
+    if !comparison.enabled {
+        for entity in &q_comparison_camera {
+            commands.entity(entity).despawn();
+        }
+        return;
+    }
+
+    if q_comparison_camera.is_empty() {
+        // Same camera as the main one, without the settings so it does not
+        // react to input and only follows the main camera
+        let camera = PanOrbitCameraBundle::new();
+        commands.spawn((
+            ComparisonCamera,
+            Camera {
+                // Renders before the main camera to prevent ambiguities
+                order: -1,
+                ..default()
+            },
+            camera.camera,
+            camera.hdr,
+            camera.state,
+            camera.atmosphere,
+            camera.exposure,
+            camera.bloom,
+            RenderLayers::layer(RIGHT_LAYER),
+        ));
+    }
+
+    // Forget the files removed from the scene
+    let is_scene_file = |entity: &Option<Entity>| entity.is_some_and(|e| q_files.contains(e));
+    if !is_scene_file(&comparison.left) {
+        comparison.left = None;
+    }
+    if !is_scene_file(&comparison.right) {
+        comparison.right = None;
+    }
+
+    let mut entities: Vec<(Entity, bool)> = q_files.iter().collect();
+    // Most recent first
+    entities.sort_by(|a, b| b.0.cmp(&a.0));
+    if comparison.left.is_none() {
+        comparison.left = entities
+            .iter()
+            .find(|(entity, is_current)| *is_current && comparison.right != Some(*entity))
+            .or_else(|| {
+                entities
+                    .iter()
+                    .find(|(entity, _)| comparison.right != Some(*entity))
+            })
+            .map(|(entity, _)| *entity);
+    }
+    if comparison.right.is_none() {
+        comparison.right = entities
+            .iter()
+            .find(|(entity, _)| comparison.left != Some(*entity))
+            .map(|(entity, _)| *entity);
+    }
+}
+
+/// Copies the camera state of the main camera to the comparison camera.
+fn sync_comparison_camera(
+    q_main_camera: Query<(&PanOrbitState, &Transform), With<PanOrbitSettings>>,
+    mut q_comparison_camera: Query<
+        (&mut PanOrbitState, &mut Transform),
+        (With<ComparisonCamera>, Without<PanOrbitSettings>),
+    >,
+) {
+    let Ok((main_state, main_transform)) = q_main_camera.single() else {
+        return;
+    };
+    for (mut state, mut transform) in &mut q_comparison_camera {
+        state.center = main_state.center;
+        state.radius = main_state.radius;
+        state.upside_down = main_state.upside_down;
+        state.pitch = main_state.pitch;
+        state.yaw = main_state.yaw;
+        *transform = *main_transform;
+    }
+}
+
+/// Applies the render layers of each scene file to all the entities of its
+/// scene, including the ones spawned since the last frame.
+fn apply_render_layers(
+    mut commands: Commands,
+    comparison: Res<Comparison>,
+    q_files: Query<Entity, With<SceneFile>>,
+    q_children: Query<&Children>,
+    q_layers: Query<Option<&RenderLayers>>,
+) {
+    for file_entity in &q_files {
+        let layers = comparison.get_render_layers(file_entity);
+        let entities = std::iter::once(file_entity).chain(q_children.iter_descendants(file_entity));
+        for entity in entities {
+            let Ok(current_layers) = q_layers.get(entity) else {
+                continue;
+            };
+            match &layers {
+                Some(layers) if current_layers != Some(layers) => {
+                    commands.entity(entity).insert(layers.clone());
+                }
+                None if current_layers.is_some() => {
+                    commands.entity(entity).remove::<RenderLayers>();
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Flags the comparison statistics as outdated when the compared files change,
+/// or when meshes are spawned or despawned as scenes load.
+fn invalidate_comparison_stats(
+    comparison: Res<Comparison>,
+    q_added: Query<(), Or<(Added<Mesh3d>, Added<Model>, Added<WorldModel>)>>,
+    mut removed_meshes: RemovedComponents<Mesh3d>,
+    mut stats: ResMut<ComparisonStats>,
+) {
+    let removed = removed_meshes.read().count() > 0;
+    if comparison.enabled && (comparison.is_changed() || removed || !q_added.is_empty()) {
+        stats.outdated = true;
+    }
+}
+
+fn update_comparison_stats(world: &mut World) {
+    let comparison = world.resource::<Comparison>();
+    let (left, right) = (comparison.left, comparison.right);
+    let get_stats = |entity: Option<Entity>| {
+        entity
+            .map(|entity| SceneStats::new(world, entity))
+            .unwrap_or_default()
+    };
+    let stats = ComparisonStats {
+        left: get_stats(left),
+        right: get_stats(right),
+        outdated: false,
+    };
+    world.insert_resource(stats);
+}
+
+/// Geometry and texture statistics of the scene of a file.
+#[derive(Default)]
+pub struct SceneStats {
+    pub mesh_count: usize,
+    pub vertex_count: usize,
+    pub triangle_count: usize,
+    /// Paths of the textures, sorted.
+    pub textures: Vec<String>,
+}
+
+impl SceneStats {
+    pub fn new(world: &World, file_entity: Entity) -> Self {
+        let meshes = world.resource::<Assets<Mesh>>();
+        let asset_server = world.resource::<AssetServer>();
+
+        let mut stats = Self::default();
+        let mut images: Vec<&Handle<Image>> = Vec::new();
+        let mut entities = vec![file_entity];
+        while let Some(entity) = entities.pop() {
+            let entity = world.entity(entity);
+            if let Some(children) = entity.get::<Children>() {
+                entities.extend(children.iter());
+            }
+            if let Some(mesh) = entity.get::<Mesh3d>().and_then(|mesh| meshes.get(mesh)) {
+                let vertex_count = mesh.count_vertices();
+                stats.mesh_count += 1;
+                stats.vertex_count += vertex_count;
+                stats.triangle_count += mesh.indices().map_or(vertex_count, |i| i.len()) / 3;
+            }
+            if let Some(model) = entity.get::<Model>() {
+                images.extend(&model.images);
+            }
+            if let Some(world_model) = entity.get::<WorldModel>() {
+                images.extend(&world_model.images);
+            }
+            if let Some(terrain_material) = entity.get::<TerrainMaterial>() {
+                images.extend(
+                    [
+                        &terrain_material.level1_texture,
+                        &terrain_material.level2_texture,
+                        &terrain_material.level3_texture,
+                    ]
+                    .into_iter()
+                    .flatten(),
+                );
+            }
+        }
+
+        stats.textures = images
+            .into_iter()
+            .filter_map(|image| asset_server.get_path(image))
+            .map(|path| path.path().display().to_string())
+            .collect();
+        stats.textures.sort();
+        stats.textures.dedup();
+        stats
+    }
+}
@@ -2,150 +2,148 @@
 // Author: Nocthir <nocthir@proton.me>
 // SPDX-License-Identifier: MIT or Apache-2.0
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::path::Path;
 use std::path::PathBuf;
+use std::sync::Arc;
 
 use bevy::prelude::*;
 use bevy::tasks;
+use serde::{Deserialize, Serialize};
 use wow_mpq as mpq;
 
 use crate::assets::*;
+use crate::data::file::{FILE_CLASSES, FileCategory, FileInfoMap};
+use crate::data::listfile::Listfile;
 
 #[derive(Default, Resource, Reflect)]
 pub struct ArchiveInfoMap {
     pub map: HashMap<PathBuf, ArchiveInfo>,
 }
 
-#[derive(Reflect)]
+#[derive(Reflect, Clone, Serialize, Deserialize)]
 pub struct ArchiveInfo {
     pub path: PathBuf,
-    pub texture_paths: Vec<String>,
-    pub model_paths: Vec<String>,
-    pub world_model_paths: Vec<String>,
-    pub world_map_paths: Vec<String>,
-    pub data_base_paths: Vec<String>,
+    /// Paths of the files of each category, indexed by [`FileCategory`].
+    pub file_paths: Vec<Vec<String>>,
+    /// Number of archive entries whose name is not known.
+    pub unnamed_count: usize,
 }
 
 impl ArchiveInfo {
     pub fn new<P: AsRef<Path>>(path: P) -> Result<Self> {
         let mut archive = mpq::Archive::open(&path)?;
-        let texture_paths = Self::get_texture_paths(&mut archive)?;
-        let model_paths = Self::get_model_paths(&mut archive)?;
-        let world_model_paths = Self::get_world_model_paths(&mut archive)?;
-        let world_map_paths = Self::get_world_map_paths(&mut archive)?;
-        let data_base_paths = Self::get_data_base_paths(&mut archive)?;
-        Ok(Self {
-            path: path.as_ref().into(),
-            texture_paths,
-            model_paths,
-            world_model_paths,
-            world_map_paths,
-            data_base_paths,
-        })
-    }
-
-    fn get_texture_paths(archive: &mut mpq::Archive) -> Result<Vec<String>> {
-        let mut textures = Vec::new();
-        archive.list()?.retain(|file| {
-            if file.name.to_lowercase().ends_with(".blp") {
-                textures.push(file.name.clone());
-                false
-            } else {
-                true
-            }
-        });
-        Ok(textures)
-    }
-
-    fn get_model_paths(archive: &mut mpq::Archive) -> Result<Vec<String>> {
-        let mut models = Vec::new();
-        archive.list()?.retain(|file| {
-            if model::is_model_extension(&file.name) {
-                models.push(file.name.clone());
-                false
-            } else {
-                true
-            }
-        });
-        Ok(models)
+        let listing = Listfile::default().list_archive(&mut archive)?;
+        Ok(Self::from_file_names(
+            path,
+            &listing.file_names,
+            listing.unnamed_count,
+        ))
     }
 
-    fn get_world_model_paths(archive: &mut mpq::Archive) -> Result<Vec<String>> {
-        let mut world_models = Vec::new();
-        archive.list()?.retain(|file| {
+    /// Categorizes the files of an archive in a single pass over its listing.
+    pub fn from_file_names<P: AsRef<Path>>(
+        path: P,
+        file_names: &[String],
+        unnamed_count: usize,
+    ) -> Self {
+        let mut file_paths = vec![Vec::new(); FILE_CLASSES.len()];
+        for file_name in file_names {
+            let Some(category) = FileCategory::from_path(file_name) else {
+                continue;
+            };
             // We only want the root .wmo files, not the group files
-            if world_model::is_world_model_root_path(&file.name) {
-                world_models.push(file.name.clone());
-                false
-            } else {
-                true
+            if category == FileCategory::WorldModel
+                && world_model::is_world_model_group_path(file_name)
+            {
+                continue;
             }
-        });
-        Ok(world_models)
+            file_paths[category.index()].push(file_name.clone());
+        }
+        Self {
+            path: path.as_ref().into(),
+            file_paths,
+            unnamed_count,
+        }
     }
 
-    fn get_world_map_paths(archive: &mut mpq::Archive) -> Result<Vec<String>> {
-        let mut world_maps = Vec::new();
-        archive.list()?.retain(|file| {
-            if world_map::is_world_map_extension(&file.name) {
-                world_maps.push(file.name.clone());
-                false
-            } else {
-                true
-            }
-        });
-        Ok(world_maps)
+    pub fn get_file_paths(&self, category: FileCategory) -> &[String] {
+        &self.file_paths[category.index()]
     }
+}
 
-    fn get_data_base_paths(archive: &mut mpq::Archive) -> Result<Vec<String>> {
-        let mut data_bases = Vec::new();
-        archive.list()?.retain(|file| {
-            if data_base::is_data_base_extension(&file.name) {
-                data_bases.push(file.name.clone());
-                false
-            } else {
-                true
-            }
-        });
-        Ok(data_bases)
+/// Archives that could not be opened or listed, with their error message.
+/// Their files are not available, but every other archive stays browsable.
+#[derive(Default, Resource)]
+pub struct ArchiveErrorMap {
+    pub map: BTreeMap<PathBuf, String>,
+}
+
+impl ArchiveErrorMap {
+    pub fn insert(&mut self, error: archive::ArchiveError) {
+        self.map.insert(error.path, error.message);
     }
 }
 
 #[derive(Resource, Default)]
 pub struct LoadArchiveTasks {
-    tasks: Vec<tasks::Task<Result<ArchiveInfo>>>,
+    tasks: Vec<tasks::Task<(PathBuf, Result<ArchiveInfo>)>>,
 }
 
-pub fn start_loading(mut commands: Commands) -> Result<()> {
+/// Rebuilds the file and archive maps from the current file archive map.
+/// Replacing the loading tasks drops the ones of a previous game installation.
+pub fn start_loading(
+    mut commands: Commands,
+    registry: Res<archive::ArchiveRegistry>,
+    mut file_map: ResMut<FileInfoMap>,
+    mut archive_info_map: ResMut<ArchiveInfoMap>,
+    mut archive_error_map: ResMut<ArchiveErrorMap>,
+) -> Result<()> {
+    let file_archive_map = registry.get();
+    *file_map = FileInfoMap::new(&file_archive_map)?;
+    archive_info_map.map.clear();
+    archive_error_map.map.clear();
+    for error in &file_archive_map.errors {
+        archive_error_map.insert(error.clone());
+    }
+
     let mut tasks = LoadArchiveTasks::default();
-    for archive_path in archive::get_archive_paths()? {
-        let task = tasks::IoTaskPool::get().spawn(load_archive(archive_path.clone()));
+    for archive_path in &file_archive_map.archive_paths {
+        let task = tasks::IoTaskPool::get()
+            .spawn(load_archive(file_archive_map.clone(), archive_path.clone()));
         tasks.tasks.push(task);
     }
     commands.insert_resource(tasks);
     Ok(())
 }
 
-async fn load_archive(archive_path: PathBuf) -> Result<ArchiveInfo> {
-    ArchiveInfo::new(archive_path)
+async fn load_archive(
+    file_archive_map: Arc<archive::FileArchiveMap>,
+    archive_path: PathBuf,
+) -> (PathBuf, Result<ArchiveInfo>) {
+    // The archive index has been built when filling the file archive map
+    if let Some(index) = file_archive_map.get_archive_index(&archive_path) {
+        return (archive_path, Ok(index.info.clone()));
+    }
+    let result = ArchiveInfo::new(&archive_path);
+    (archive_path, result)
 }
 
 pub fn check_archive_loading(
-    mut exit: MessageWriter<AppExit>,
     mut load_task: ResMut<LoadArchiveTasks>,
     mut archive_info_map: ResMut<ArchiveInfoMap>,
+    mut archive_error_map: ResMut<ArchiveErrorMap>,
 ) -> Result<()> {
     let mut tasks = Vec::new();
     tasks.append(&mut load_task.tasks);
 
     for mut current_task in tasks {
         let poll_result = tasks::block_on(tasks::poll_once(&mut current_task));
-        if let Some(result) = poll_result {
+        if let Some((archive_path, result)) = poll_result {
             match result {
                 Err(err) => {
-                    error!("Error loading archive: {err}");
-                    exit.write(AppExit::error());
+                    error!("Error loading archive {}: {err}", archive_path.display());
+                    archive_error_map.insert(archive::ArchiveError::new(archive_path, err));
                 }
                 Ok(archive) => {
                     info!("Loaded archive info: {}", archive.path.display());
@@ -0,0 +1,217 @@
+// Copyright © 2025
+// Author: Nocthir <nocthir@proton.me>
+// SPDX-License-Identifier: MIT or Apache-2.0
+
+//! Persistent index of the archive listings.
+//!
+//! Listing an archive requires parsing its header, tables and listfile, which
+//! takes a long time on a full client install. The listing and categorized file
+//! lists of every archive are stored in a cache file, and an archive is only
+//! listed again when its size or modification time, or the external listfiles
+//! it has been checked against, change.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::SystemTime;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use wow_mpq as mpq;
+
+use crate::assets::archive::ArchiveError;
+use crate::data::archive::ArchiveInfo;
+use crate::data::listfile::{Listfile, ListfileStamp};
+
+/// Default location of the cache file, used by the viewer.
+pub const CACHE_PATH: &str = "assets/archive_cache.json";
+
+#[derive(Serialize, Deserialize)]
+pub struct ArchiveIndex {
+    pub size: u64,
+    pub modified: SystemTime,
+    /// Names of all the files listed by the archive, or found through the
+    /// external listfiles.
+    pub file_names: Vec<String>,
+    pub info: ArchiveInfo,
+    /// External listfiles the archive has been checked against.
+    pub listfiles: Vec<ListfileStamp>,
+    /// Normalized path of the files of the archive scanned for references →
+    /// paths of the files they reference, `None` when the scan failed.
+    #[serde(default)]
+    pub references: HashMap<String, Option<Vec<String>>>,
+}
+
+impl ArchiveIndex {
+    pub fn new<P: AsRef<Path>>(
+        archive_path: P,
+        metadata: &fs::Metadata,
+        listfile: &Listfile,
+    ) -> Result<Self> {
+        let mut archive = mpq::Archive::open(&archive_path)?;
+        let listing = listfile.list_archive(&mut archive)?;
+        let info =
+            ArchiveInfo::from_file_names(archive_path, &listing.file_names, listing.unnamed_count);
+        Ok(Self {
+            size: metadata.len(),
+            modified: metadata.modified()?,
+            file_names: listing.file_names,
+            info,
+            listfiles: listing.listfiles,
+            references: HashMap::new(),
+        })
+    }
+
+    /// Whether both indexes have been built from the same version of the archive.
+    fn has_same_stamp(&self, other: &ArchiveIndex) -> bool {
+        self.size == other.size && self.modified == other.modified
+    }
+
+    fn is_up_to_date(&self, metadata: &fs::Metadata) -> bool {
+        self.size == metadata.len()
+            && metadata
+                .modified()
+                .is_ok_and(|modified| modified == self.modified)
+    }
+
+    /// Whether the listing still matches the external listfiles: none of the
+    /// listfiles it has been checked against changed, and when entries are left
+    /// unnamed, there is no new listfile that could name them.
+    fn is_listing_up_to_date(&self, listfile: &Listfile) -> bool {
+        let stamps = listfile.stamps();
+        self.listfiles.iter().all(|stamp| stamps.contains(stamp))
+            && (self.info.unnamed_count == 0
+                || stamps.iter().all(|stamp| self.listfiles.contains(stamp)))
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ArchiveIndexCache {
+    version: u32,
+    archives: HashMap<PathBuf, ArchiveIndex>,
+}
+
+impl Default for ArchiveIndexCache {
+    fn default() -> Self {
+        Self {
+            version: Self::VERSION,
+            archives: HashMap::new(),
+        }
+    }
+}
+
+impl ArchiveIndexCache {
+    /// Must be increased whenever the cached data or the file categorization changes.
+    const VERSION: u32 = 5;
+
+    /// Loads the cache file, returning an empty cache when it is missing or outdated.
+    pub fn load<P: AsRef<Path>>(cache_path: P) -> Self {
+        match Self::read(cache_path) {
+            Ok(cache) if cache.version == Self::VERSION => cache,
+            Ok(_) => {
+                info!("Archive index cache version changed, rebuilding it");
+                Self::default()
+            }
+            Err(err) => {
+                info!("No archive index cache available: {err}");
+                Self::default()
+            }
+        }
+    }
+
+    fn read<P: AsRef<Path>>(cache_path: P) -> Result<Self> {
+        let file = fs::read(cache_path)?;
+        Ok(serde_json::from_slice(&file)?)
+    }
+
+    /// Writes the cache to a temporary file next to `cache_path`, then renames
+    /// it over the cache, so an interrupted save never leaves a truncated cache.
+    pub fn save<P: AsRef<Path>>(&self, cache_path: P) -> Result<()> {
+        // Concurrent saves, e.g. of the references and of another installation,
+        // each write their own temporary file
+        static SAVE_COUNT: AtomicU64 = AtomicU64::new(0);
+        let cache_path = cache_path.as_ref();
+        let mut temp_path = cache_path.as_os_str().to_owned();
+        temp_path.push(format!(
+            ".{}-{}.tmp",
+            std::process::id(),
+            SAVE_COUNT.fetch_add(1, Ordering::Relaxed)
+        ));
+
+        let file = serde_json::to_vec(self)?;
+        if let Err(err) =
+            fs::write(&temp_path, file).and_then(|_| fs::rename(&temp_path, cache_path))
+        {
+            let _ = fs::remove_file(&temp_path);
+            return Err(err.into());
+        }
+        Ok(())
+    }
+
+    /// Makes sure the cache holds an up to date index for every archive in
+    /// `archive_paths`. The indexes of other installations are kept, as long as
+    /// their archive still exists unchanged. Archives that cannot be indexed are
+    /// left out of the cache and reported in `errors`. When an external listfile
+    /// changes, only the archives checked against it are indexed again.
+    /// Returns whether the cache changed.
+    pub fn update(
+        &mut self,
+        archive_paths: &[PathBuf],
+        listfile: &Listfile,
+        errors: &mut Vec<ArchiveError>,
+    ) -> bool {
+        let mut changed = false;
+        let archive_count = self.archives.len();
+        self.archives.retain(|archive_path, index| {
+            archive_paths.contains(archive_path)
+                || fs::metadata(archive_path).is_ok_and(|metadata| index.is_up_to_date(&metadata))
+        });
+        changed |= archive_count != self.archives.len();
+
+        for archive_path in archive_paths {
+            match self.update_archive(archive_path, listfile) {
+                Ok(updated) => changed |= updated,
+                Err(err) => {
+                    error!("Failed to index archive {}: {err}", archive_path.display());
+                    changed |= self.archives.remove(archive_path).is_some();
+                    errors.push(ArchiveError::new(archive_path, err));
+                }
+            }
+        }
+
+        changed
+    }
+
+    fn update_archive(&mut self, archive_path: &Path, listfile: &Listfile) -> Result<bool> {
+        let metadata = fs::metadata(archive_path)?;
+        if self.archives.get(archive_path).is_some_and(|index| {
+            index.is_up_to_date(&metadata) && index.is_listing_up_to_date(listfile)
+        }) {
+            return Ok(false);
+        }
+        info!("Indexing archive: {}", archive_path.display());
+        let index = ArchiveIndex::new(archive_path, &metadata, listfile)?;
+        self.archives.insert(archive_path.to_path_buf(), index);
+        Ok(true)
+    }
+
+    pub fn get(&self, archive_path: &Path) -> Option<&ArchiveIndex> {
+        self.archives.get(archive_path)
+    }
+
+    /// Stores the references of files scanned from the archive `scanned` has
+    /// been built from. They are dropped when the archive changed since.
+    pub fn insert_references(
+        &mut self,
+        archive_path: &Path,
+        scanned: &ArchiveIndex,
+        references: impl IntoIterator<Item = (String, Option<Vec<String>>)>,
+    ) {
+        if let Some(index) = self.archives.get_mut(archive_path)
+            && index.has_same_stamp(scanned)
+        {
+            index.references.extend(references);
+        }
+    }
+}
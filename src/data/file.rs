@@ -8,7 +8,6 @@ use std::{
 };
 
 use bevy::{asset::RecursiveDependencyLoadState, prelude::*};
-use wow_mpq as mpq;
 
 use crate::assets::*;
 
@@ -53,6 +52,7 @@ pub enum DataType {
     WorldModel(Handle<world_model::WorldModelAsset>),
     WorldMap(Handle<world_map::WorldMapAsset>),
     DataBase(Handle<data_base::DataBaseAsset>),
+    Raw(FileCategory, Handle<raw::RawAsset>),
     Unknown,
 }
 
@@ -65,6 +65,7 @@ impl DataType {
             DataType::WorldModel(h) => *h = handle.typed(),
             DataType::WorldMap(h) => *h = handle.typed(),
             DataType::DataBase(h) => *h = handle.typed(),
+            DataType::Raw(_, h) => *h = handle.typed(),
             DataType::Unknown => {}
         }
     }
@@ -77,6 +78,7 @@ impl DataType {
             DataType::WorldModel(handle) => *handle = asset_server.load(path),
             DataType::WorldMap(handle) => *handle = asset_server.load(path),
             DataType::DataBase(handle) => *handle = asset_server.load(path),
+            DataType::Raw(_, handle) => *handle = asset_server.load(path),
             DataType::Unknown => (),
         };
     }
@@ -88,10 +90,23 @@ impl DataType {
             DataType::WorldModel(handle) => *handle = Handle::default(),
             DataType::WorldMap(handle) => *handle = Handle::default(),
             DataType::DataBase(handle) => *handle = Handle::default(),
+            DataType::Raw(_, handle) => *handle = Handle::default(),
             DataType::Unknown => (),
         };
     }
 
+    pub fn category(&self) -> Option<FileCategory> {
+        match self {
+            DataType::Texture(_) => Some(FileCategory::Texture),
+            DataType::Model(_) => Some(FileCategory::Model),
+            DataType::WorldModel(_) => Some(FileCategory::WorldModel),
+            DataType::WorldMap(_) => Some(FileCategory::WorldMap),
+            DataType::DataBase(_) => Some(FileCategory::DataBase),
+            DataType::Raw(category, _) => Some(*category),
+            DataType::Unknown => None,
+        }
+    }
+
     pub fn state(&self, asset_server: &AssetServer) -> RecursiveDependencyLoadState {
         let ret = match self {
             DataType::Texture(handle) => asset_server.get_recursive_dependency_load_state(handle),
@@ -101,6 +116,7 @@ impl DataType {
             }
             DataType::WorldMap(handle) => asset_server.get_recursive_dependency_load_state(handle),
             DataType::DataBase(handle) => asset_server.get_recursive_dependency_load_state(handle),
+            DataType::Raw(_, handle) => asset_server.get_recursive_dependency_load_state(handle),
             DataType::Unknown => None,
         };
         ret.unwrap_or(RecursiveDependencyLoadState::NotLoaded)
@@ -109,37 +125,229 @@ impl DataType {
 
 impl<S: Into<String>> From<S> for DataType {
     fn from(file_path: S) -> Self {
-        let lowercase = file_path.into().to_lowercase();
-        if lowercase.ends_with(".blp") {
-            DataType::Texture(Handle::default())
-        } else if lowercase.ends_with(".m2") {
-            DataType::Model(Handle::default())
-        } else if lowercase.ends_with(".wmo") {
-            DataType::WorldModel(Handle::default())
-        } else if lowercase.ends_with(".adt") {
-            DataType::WorldMap(Handle::default())
-        } else if lowercase.ends_with(".dbc") {
-            DataType::DataBase(Handle::default())
-        } else {
-            warn!("Unknown file extension: {}", lowercase);
-            DataType::Unknown
+        let file_path = file_path.into();
+        match FileCategory::from_path(&file_path) {
+            Some(FileCategory::Texture) => DataType::Texture(Handle::default()),
+            Some(FileCategory::Model) => DataType::Model(Handle::default()),
+            Some(FileCategory::WorldModel) => DataType::WorldModel(Handle::default()),
+            Some(FileCategory::WorldMap) => DataType::WorldMap(Handle::default()),
+            Some(FileCategory::DataBase) => DataType::DataBase(Handle::default()),
+            Some(category) => DataType::Raw(category, Handle::default()),
+            None => {
+                warn!("Unknown file extension: {}", file_path);
+                DataType::Unknown
+            }
         }
     }
 }
 
-#[derive(Resource)]
+/// Category of a file, derived from its extension.
+///
+/// Variants must follow the order of [`FILE_CLASSES`].
+#[derive(Reflect, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FileCategory {
+    Texture,
+    Model,
+    WorldModel,
+    WorldMap,
+    DataBase,
+    WorldTable,
+    WorldLowRes,
+    Skin,
+    Animation,
+    Shader,
+    Light,
+    ZoneMap,
+    Sound,
+    Interface,
+    Font,
+    Translation,
+}
+
+impl FileCategory {
+    pub fn from_path(file_path: &str) -> Option<Self> {
+        let (_, extension) = file_path.rsplit_once('.')?;
+        let extension = extension.to_ascii_lowercase();
+        FILE_CLASSES
+            .iter()
+            .find(|class| class.extensions.contains(&extension.as_str()))
+            .map(|class| class.category)
+    }
+
+    pub fn index(self) -> usize {
+        self as usize
+    }
+
+    pub fn class(self) -> &'static FileClass {
+        &FILE_CLASSES[self.index()]
+    }
+}
+
+/// How the files of a category are recognized and presented.
+pub struct FileClass {
+    pub category: FileCategory,
+    /// Lowercase extensions, without the leading dot.
+    pub extensions: &'static [&'static str],
+    /// Label of the category in the archive browser.
+    pub label: &'static str,
+    pub icon: &'static str,
+    /// Whether files of this category are spawned as a scene root when selected.
+    pub has_scene_root: bool,
+    /// Whether files of this category have a dedicated loader. The other ones
+    /// are loaded as [`raw::RawAsset`] for a generic info and hex view.
+    pub has_loader: bool,
+}
+
+/// Classifier table shared by the file map, the archive lists and the UI.
+pub static FILE_CLASSES: &[FileClass] = &[
+    FileClass {
+        category: FileCategory::Texture,
+        extensions: &["blp"],
+        label: "Textures",
+        icon: "🖼",
+        has_scene_root: false,
+        has_loader: true,
+    },
+    FileClass {
+        category: FileCategory::Model,
+        extensions: &["m2", "mdx", "mdl"],
+        label: "Models",
+        icon: "📦",
+        has_scene_root: true,
+        has_loader: true,
+    },
+    FileClass {
+        category: FileCategory::WorldModel,
+        extensions: &["wmo"],
+        label: "World Models",
+        icon: "🏰",
+        has_scene_root: true,
+        has_loader: true,
+    },
+    FileClass {
+        category: FileCategory::WorldMap,
+        extensions: &["adt"],
+        label: "World Maps",
+        icon: "🗺",
+        has_scene_root: true,
+        has_loader: true,
+    },
+    FileClass {
+        category: FileCategory::DataBase,
+        extensions: &["dbc"],
+        label: "Data Bases",
+        icon: "📚",
+        has_scene_root: false,
+        has_loader: true,
+    },
+    FileClass {
+        category: FileCategory::WorldTable,
+        extensions: &["wdt"],
+        label: "World Tables",
+        icon: "🌐",
+        has_scene_root: false,
+        has_loader: false,
+    },
+    FileClass {
+        category: FileCategory::WorldLowRes,
+        extensions: &["wdl"],
+        label: "Low Detail Maps",
+        icon: "🗾",
+        has_scene_root: false,
+        has_loader: false,
+    },
+    FileClass {
+        category: FileCategory::Skin,
+        extensions: &["skin"],
+        label: "Model Skins",
+        icon: "🧩",
+        has_scene_root: false,
+        has_loader: false,
+    },
+    FileClass {
+        category: FileCategory::Animation,
+        extensions: &["anim"],
+        label: "Animations",
+        icon: "🎞",
+        has_scene_root: false,
+        has_loader: false,
+    },
+    FileClass {
+        category: FileCategory::Shader,
+        extensions: &["bls"],
+        label: "Shaders",
+        icon: "✨",
+        has_scene_root: false,
+        has_loader: false,
+    },
+    FileClass {
+        category: FileCategory::Light,
+        extensions: &["lit"],
+        label: "Lights",
+        icon: "💡",
+        has_scene_root: false,
+        has_loader: false,
+    },
+    FileClass {
+        category: FileCategory::ZoneMap,
+        extensions: &["zmp"],
+        label: "Zone Maps",
+        icon: "🧭",
+        has_scene_root: false,
+        has_loader: false,
+    },
+    FileClass {
+        category: FileCategory::Sound,
+        extensions: &["wav", "mp3"],
+        label: "Sounds",
+        icon: "🔊",
+        has_scene_root: false,
+        has_loader: false,
+    },
+    FileClass {
+        category: FileCategory::Interface,
+        extensions: &["lua", "xml", "toc"],
+        label: "Interface",
+        icon: "📜",
+        has_scene_root: false,
+        has_loader: false,
+    },
+    FileClass {
+        category: FileCategory::Font,
+        extensions: &["ttf"],
+        label: "Fonts",
+        icon: "🔤",
+        has_scene_root: false,
+        has_loader: false,
+    },
+    FileClass {
+        category: FileCategory::Translation,
+        extensions: &["trs"],
+        label: "Minimap Translations",
+        icon: "🔀",
+        has_scene_root: false,
+        has_loader: false,
+    },
+];
+
+#[derive(Resource, Default)]
 pub struct FileInfoMap {
+    /// Lowercase path → file.
     map: HashMap<String, FileInfo>,
 }
 
 impl FileInfoMap {
-    pub fn new() -> Result<Self> {
+    pub fn new(file_archive_map: &archive::FileArchiveMap) -> Result<Self> {
+        // Reuse the archive listings of the file archive map
         let mut map = HashMap::new();
-        for archive_path in archive::get_archive_paths()? {
-            let mut archive = mpq::Archive::open(&archive_path)?;
-            for file_path in archive.list()? {
-                let info = FileInfo::new(file_path.name.clone(), &archive_path);
-                map.insert(file_path.name.to_lowercase(), info);
+        // Archive paths are sorted by priority, so patches override base archives
+        for archive_path in &file_archive_map.archive_paths {
+            let index = file_archive_map
+                .get_archive_index(archive_path)
+                .ok_or(format!("Archive `{}` not indexed", archive_path.display()))?;
+            for file_name in &index.file_names {
+                let info = FileInfo::new(file_name.clone(), archive_path);
+                map.insert(file_name.to_lowercase(), info);
             }
         }
         Ok(Self { map })
@@ -152,12 +360,16 @@ impl FileInfoMap {
             .ok_or(format!("File `{}` not found in file map", file_path).into())
     }
 
-    // Actually used in tests
-    #[allow(unused)]
     pub fn get_files(&self) -> impl Iterator<Item = &FileInfo> {
         self.map.values()
     }
 
+    /// Returns the files with their lowercase path, computed once when the map
+    /// is built, so searches do not lowercase every path again.
+    pub fn get_files_by_lowercase_path(&self) -> impl Iterator<Item = (&str, &FileInfo)> {
+        self.map.iter().map(|(path, file)| (path.as_str(), file))
+    }
+
     pub fn insert(&mut self, file_info: FileInfo) {
         self.map.insert(file_info.path.to_lowercase(), file_info);
     }
@@ -169,3 +381,36 @@ impl FileInfoMap {
             .ok_or(format!("File `{}` not found", file_path).into())
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_file_classes_order() {
+        for (index, class) in FILE_CLASSES.iter().enumerate() {
+            assert_eq!(class.category.index(), index);
+        }
+    }
+
+    #[test]
+    fn test_file_category() {
+        assert_eq!(
+            FileCategory::from_path("Creature\\Wolf\\Wolf.M2"),
+            Some(FileCategory::Model)
+        );
+        assert_eq!(
+            FileCategory::from_path("World\\Maps\\Azeroth\\Azeroth_32_48.adt"),
+            Some(FileCategory::WorldMap)
+        );
+        assert_eq!(
+            FileCategory::from_path("Interface\\FrameXML\\FrameXML.toc"),
+            Some(FileCategory::Interface)
+        );
+        assert_eq!(
+            FileCategory::from_path("Sound\\Music\\ZoneMusic\\Forest.MP3"),
+            Some(FileCategory::Sound)
+        );
+        assert_eq!(FileCategory::from_path("Interface\\FrameXML"), None);
+    }
+}
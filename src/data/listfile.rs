@@ -0,0 +1,178 @@
+// Copyright © 2025
+// Author: Nocthir <nocthir@proton.me>
+// SPDX-License-Identifier: MIT or Apache-2.0
+
+//! External listfiles.
+//!
+//! Archives only store hashes of their file names, and the names themselves
+//! are only known through the internal `(listfile)`. Stripped or custom archives
+//! lack it, so their files are looked up by the names of external listfiles,
+//! one file name per line.
+
+use std::cell::OnceCell;
+use std::collections::HashSet;
+use std::fs;
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use wow_mpq as mpq;
+
+use crate::assets::archive::ArchiveError;
+use crate::settings::Settings;
+
+/// Entries holding archive metadata instead of client files.
+const SPECIAL_FILE_NAMES: [&str; 3] = ["(listfile)", "(attributes)", "(signature)"];
+
+/// Identifies the version of a listfile, so archives are indexed again when it changes.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ListfileStamp {
+    pub path: PathBuf,
+    pub size: u64,
+    pub modified: SystemTime,
+}
+
+/// External listfiles configured in the settings.
+/// Their names are only read when an archive needs them.
+#[derive(Default)]
+pub struct Listfile {
+    stamps: Vec<ListfileStamp>,
+    names: OnceCell<Vec<String>>,
+}
+
+impl Listfile {
+    /// Listfiles that can not be found are reported in `errors` and skipped.
+    pub fn new(settings: &Settings, errors: &mut Vec<ArchiveError>) -> Self {
+        let mut stamps = Vec::new();
+        for listfile_path in &settings.listfile_paths {
+            match Self::get_stamp(listfile_path) {
+                Ok(stamp) => stamps.push(stamp),
+                Err(err) => {
+                    error!("Failed to find listfile {listfile_path}: {err}");
+                    errors.push(ArchiveError::new(listfile_path, err));
+                }
+            }
+        }
+        Self {
+            stamps,
+            names: OnceCell::new(),
+        }
+    }
+
+    fn get_stamp(listfile_path: &str) -> Result<ListfileStamp> {
+        let metadata = fs::metadata(listfile_path)?;
+        Ok(ListfileStamp {
+            path: listfile_path.into(),
+            size: metadata.len(),
+            modified: metadata.modified()?,
+        })
+    }
+
+    pub fn stamps(&self) -> &[ListfileStamp] {
+        &self.stamps
+    }
+
+    /// Returns the file names of all the listfiles, with backslash separators.
+    pub fn names(&self) -> &[String] {
+        self.names.get_or_init(|| {
+            let mut names = Vec::new();
+            for stamp in &self.stamps {
+                match fs::read_to_string(&stamp.path) {
+                    Ok(text) => names.extend(
+                        text.lines()
+                            .map(str::trim)
+                            .filter(|name| !name.is_empty())
+                            .map(|name| name.replace('/', "\\")),
+                    ),
+                    Err(err) => error!("Failed to read listfile {}: {err}", stamp.path.display()),
+                }
+            }
+            info!("Loaded {} names from external listfiles", names.len());
+            names
+        })
+    }
+
+    /// Lists the files of an archive. When the internal listfile does not name
+    /// every entry, the names of the external listfiles are checked against the
+    /// hash table of the archive. The special entries, such as `(listfile)`,
+    /// are left out of the listing.
+    pub fn list_archive(&self, archive: &mut mpq::Archive) -> Result<ArchiveListing> {
+        let mut file_names: Vec<String> = archive.list()?.into_iter().map(|f| f.name).collect();
+        file_names.retain(|name| !is_special_file_name(name));
+        let mut special_count = 0;
+        for name in SPECIAL_FILE_NAMES {
+            if archive.find_file(name)?.is_some() {
+                special_count += 1;
+            }
+        }
+        let entry_count = get_file_count(archive)?.saturating_sub(special_count);
+        let mut unnamed_count = entry_count.saturating_sub(file_names.len());
+        if unnamed_count == 0 || self.stamps.is_empty() {
+            return Ok(ArchiveListing {
+                file_names,
+                unnamed_count,
+                listfiles: Vec::new(),
+            });
+        }
+
+        let mut known: HashSet<String> = file_names.iter().map(|f| f.to_lowercase()).collect();
+        for name in self.names() {
+            if unnamed_count == 0 {
+                break;
+            }
+            let lowercase_name = name.to_lowercase();
+            if known.contains(&lowercase_name) {
+                continue;
+            }
+            if archive.find_file(name)?.is_some() {
+                known.insert(lowercase_name);
+                file_names.push(name.clone());
+                unnamed_count -= 1;
+            }
+        }
+        Ok(ArchiveListing {
+            file_names,
+            unnamed_count,
+            listfiles: self.stamps.clone(),
+        })
+    }
+}
+
+/// Files of an archive, as listed by [`Listfile::list_archive`].
+pub struct ArchiveListing {
+    pub file_names: Vec<String>,
+    /// Number of entries whose name is not known.
+    pub unnamed_count: usize,
+    /// External listfiles the archive has been checked against, empty when its
+    /// internal listfile names every entry.
+    pub listfiles: Vec<ListfileStamp>,
+}
+
+fn is_special_file_name(name: &str) -> bool {
+    SPECIAL_FILE_NAMES
+        .iter()
+        .any(|special| special.eq_ignore_ascii_case(name))
+}
+
+/// Returns the number of files stored in an archive: the hash table entries
+/// pointing to an existing block. Deleted entries and unused blocks are not
+/// counted.
+fn get_file_count(archive: &mut mpq::Archive) -> Result<usize> {
+    let (Some(hash_table), Some(block_table)) = (archive.hash_table(), archive.block_table())
+    else {
+        return Ok(archive.get_info()?.file_count);
+    };
+    let block_indices: HashSet<u32> = hash_table
+        .entries()
+        .iter()
+        .filter(|entry| entry.is_valid())
+        .map(|entry| entry.block_index)
+        .filter(|&index| {
+            block_table
+                .get(index as usize)
+                .is_some_and(|block| block.exists())
+        })
+        .collect();
+    Ok(block_indices.len())
+}
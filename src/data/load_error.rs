@@ -0,0 +1,125 @@
+// Copyright © 2025
+// Author: Nocthir <nocthir@proton.me>
+// SPDX-License-Identifier: MIT or Apache-2.0
+
+//! Load failures reported by the asset server.
+//!
+//! The recursive load state of a file only tells that it or one of its
+//! dependencies failed, and is lost once the file is unloaded. The failures are
+//! recorded from `AssetLoadFailedEvent` by file path, so the reason stays
+//! available, and a file can point to the dependency that made it fail.
+
+use std::collections::HashMap;
+
+use bevy::asset::{AssetLoadError, AssetLoadFailedEvent, RecursiveDependencyLoadState};
+use bevy::prelude::*;
+
+use crate::assets::archive::normalize_path;
+use crate::data::file::FileInfo;
+
+#[derive(Debug, Clone)]
+pub struct LoadError {
+    /// Path of the file that failed to load, which may be a dependency of
+    /// the file the error is reported for.
+    pub file_path: String,
+    pub message: String,
+}
+
+impl LoadError {
+    pub fn new(asset_path: &AssetPath, err: &AssetLoadError) -> Self {
+        let message = match err {
+            // The loader error already names the file
+            AssetLoadError::AssetLoaderError(err) => err.error().to_string(),
+            err => err.to_string(),
+        };
+        Self {
+            file_path: get_file_path(asset_path),
+            message,
+        }
+    }
+}
+
+/// Last load failure of every file, by normalized file path.
+#[derive(Resource, Default)]
+pub struct LoadErrorMap {
+    map: HashMap<String, LoadError>,
+}
+
+impl LoadErrorMap {
+    pub fn get(&self, file_path: &str) -> Option<&LoadError> {
+        self.map.get(&normalize_path(file_path))
+    }
+
+    pub fn insert(&mut self, load_error: LoadError) {
+        self.map
+            .insert(normalize_path(&load_error.file_path), load_error);
+    }
+
+    pub fn remove(&mut self, file_path: &str) {
+        self.map.remove(&normalize_path(file_path));
+    }
+
+    pub fn clear(&mut self) {
+        self.map.clear();
+    }
+
+    /// Returns why a file failed to load, or its last failure when it is no
+    /// longer loaded. When a dependency failed, the error of the dependency is
+    /// returned.
+    pub fn get_file_error(
+        &self,
+        file_info: &FileInfo,
+        asset_server: &AssetServer,
+    ) -> Option<LoadError> {
+        if let Some(load_error) = self.get(&file_info.path) {
+            return Some(load_error.clone());
+        }
+        let RecursiveDependencyLoadState::Failed(err) = file_info.get_load_state(asset_server)
+        else {
+            return None;
+        };
+        let file_path = get_error_path(&err).unwrap_or_else(|| file_info.path.clone());
+        let load_error = self.get(&file_path).cloned().unwrap_or_else(|| LoadError {
+            file_path,
+            message: err.to_string(),
+        });
+        Some(load_error)
+    }
+}
+
+/// Records the load failures of the assets of type `A`, and forgets them when
+/// the asset itself loads again.
+pub fn record_load_errors<A: Asset>(
+    mut failed_reader: MessageReader<AssetLoadFailedEvent<A>>,
+    mut asset_reader: MessageReader<AssetEvent<A>>,
+    asset_server: Res<AssetServer>,
+    mut load_error_map: ResMut<LoadErrorMap>,
+) {
+    for event in asset_reader.read() {
+        if let AssetEvent::Added { id } = event
+            && let Some(asset_path) = asset_server.get_path(*id)
+        {
+            load_error_map.remove(&get_file_path(&asset_path));
+        }
+    }
+    for event in failed_reader.read() {
+        load_error_map.insert(LoadError::new(&event.path, &event.error));
+    }
+}
+
+/// Returns the archive file path of an asset path, without source nor label.
+fn get_file_path(asset_path: &AssetPath) -> String {
+    asset_path.path().to_string_lossy().replace('/', "\\")
+}
+
+/// Returns the path of the file a load error comes from, when known.
+fn get_error_path(err: &AssetLoadError) -> Option<String> {
+    let asset_path = match err {
+        AssetLoadError::AssetLoaderError(err) => err.path().clone(),
+        AssetLoadError::AssetReaderError(bevy::asset::io::AssetReaderError::NotFound(path)) => {
+            AssetPath::from_path(path).into_owned()
+        }
+        _ => return None,
+    };
+    Some(get_file_path(&asset_path))
+}
@@ -3,75 +3,296 @@
 // SPDX-License-Identifier: MIT or Apache-2.0
 
 pub mod archive;
+pub mod cache;
 pub mod file;
+pub mod listfile;
+pub mod load_error;
+pub mod references;
+pub mod validation;
 
 use bevy::prelude::*;
+use bevy::tasks;
 
-use crate::{data::archive::*, ui};
+use crate::{assets, data::archive::*, settings::Settings, ui};
 
 pub struct DataPlugin;
 
 impl Plugin for DataPlugin {
     fn build(&self, app: &mut App) {
         app.insert_resource(archive::ArchiveInfoMap::default())
-            .insert_resource(file::FileInfoMap::new().expect("Failed to create FileInfoMap"))
-            .add_systems(Startup, (archive::start_loading, ui::select_default_model))
+            .init_resource::<archive::ArchiveErrorMap>()
+            .init_resource::<file::FileInfoMap>()
+            .init_resource::<references::ReferenceIndex>()
+            .init_resource::<load_error::LoadErrorMap>()
+            .add_message::<GameIndexed>()
+            .add_systems(
+                Startup,
+                (
+                    (archive::start_loading, references::start_scanning).chain(),
+                    ui::select_default_model,
+                ),
+            )
+            .add_systems(
+                Update,
+                (
+                    switch_game_path,
+                    check_game_indexing.run_if(resource_exists::<GameIndexingTask>),
+                    (archive::start_loading, references::start_scanning)
+                        .chain()
+                        .run_if(on_message::<GameIndexed>),
+                )
+                    .chain(),
+            )
             .add_systems(
                 Update,
                 archive::check_archive_loading.run_if(resource_exists::<LoadArchiveTasks>),
             )
-            .add_systems(Update, load_selected_file);
+            .add_systems(
+                Update,
+                references::check_scanning
+                    .run_if(resource_exists::<references::ScanReferenceTasks>),
+            )
+            .add_systems(
+                Update,
+                (
+                    load_error::record_load_errors::<Image>,
+                    load_error::record_load_errors::<assets::model::ModelAsset>,
+                    load_error::record_load_errors::<assets::world_model::WorldModelAsset>,
+                    load_error::record_load_errors::<assets::world_map::WorldMapAsset>,
+                    load_error::record_load_errors::<assets::data_base::DataBaseAsset>,
+                    load_error::record_load_errors::<assets::raw::RawAsset>,
+                ),
+            )
+            .add_systems(Update, (load_selected_file, remove_scene_files).chain());
     }
 }
 
+/// Root file loaded in the scene, e.g. a model or a world map.
 #[derive(Component)]
-pub struct CurrentFile {
+pub struct SceneFile {
     pub path: String,
+    /// Pinned files stay in the scene when another file is selected.
+    pub pinned: bool,
+    /// Read from the comparison game installation instead of the current one.
+    /// These files are not in the [`file::FileInfoMap`], and never current.
+    pub comparison: bool,
 }
 
-impl CurrentFile {
+impl SceneFile {
     pub fn new(path: String) -> Self {
-        info!("Current file: {}", path);
-        Self { path }
+        info!("Scene file: {}", path);
+        Self {
+            path,
+            pinned: false,
+            comparison: false,
+        }
+    }
+
+    /// Scene file read from the comparison game installation, pinned so it
+    /// stays in the scene while browsing the current installation.
+    pub fn from_comparison(path: String) -> Self {
+        info!("Comparison scene file: {}", path);
+        Self {
+            path,
+            pinned: true,
+            comparison: true,
+        }
     }
 
     pub fn get_asset_path(&self) -> String {
-        format!("archive://{}", self.path)
+        let source = if self.comparison {
+            assets::archive::COMPARISON_SOURCE
+        } else {
+            assets::archive::ARCHIVE_SOURCE
+        };
+        format!("{source}://{}", self.path)
     }
 }
 
+/// Marks the scene file shown in the right panel, the last one selected.
+#[derive(Component)]
+pub struct CurrentFile;
+
+/// Loads the selected file in place of the unpinned scene file, or makes it
+/// the current file when it is already in the scene.
 fn load_selected_file(
     mut event_reader: MessageReader<ui::FileSelected>,
-    current_query: Query<&CurrentFile>,
-    entity_query: Query<Entity, With<CurrentFile>>,
+    scene_query: Query<(Entity, &SceneFile, Has<CurrentFile>)>,
     mut commands: Commands,
     mut asset_server: ResMut<AssetServer>,
     mut file_map: ResMut<file::FileInfoMap>,
 ) -> Result {
     // Ignore all but the last event
-    if let Some(event) = event_reader.read().last() {
-        for entity in entity_query.into_iter() {
-            let current_file = current_query.get(entity)?;
-            if current_file.path == event.file_path {
-                return Ok(());
-            }
-            // Remove the previous model
-            commands.entity(entity).despawn();
+    let Some(event) = event_reader.read().last() else {
+        return Ok(());
+    };
 
-            // Unload the previous file's asset
-            file_map.get_file_mut(&current_file.path)?.unload();
+    let selected = scene_query.iter().find(|(_, scene_file, _)| {
+        !scene_file.comparison && scene_file.path.eq_ignore_ascii_case(&event.file_path)
+    });
+    if let Some((entity, _, is_current)) = selected {
+        if !is_current {
+            for (entity, ..) in scene_query.iter().filter(|(.., is_current)| *is_current) {
+                commands.entity(entity).remove::<CurrentFile>();
+            }
+            commands.entity(entity).insert(CurrentFile);
         }
+        return Ok(());
+    }
 
-        file_map
-            .get_file_mut(&event.file_path)?
-            .load(&mut asset_server);
-        if event.has_scene_root() {
-            let handle = asset_server.load(event.get_asset_path());
-            commands.spawn((CurrentFile::new(event.file_path.clone()), SceneRoot(handle)));
-        } else {
-            let _ = asset_server.load_untyped(event.get_asset_path());
-            commands.spawn(CurrentFile::new(event.file_path.clone()));
+    for (entity, scene_file, is_current) in scene_query.iter() {
+        if scene_file.pinned {
+            if is_current {
+                commands.entity(entity).remove::<CurrentFile>();
+            }
+            continue;
         }
+        // Remove the previous model
+        commands.entity(entity).despawn();
+
+        // Unload the previous file's asset
+        file_map.get_file_mut(&scene_file.path)?.unload();
+    }
+
+    file_map
+        .get_file_mut(&event.file_path)?
+        .load(&mut asset_server);
+    let scene_file = SceneFile::new(event.file_path.clone());
+    if event.has_scene_root() {
+        let handle = asset_server.load(event.get_asset_path());
+        commands.spawn((scene_file, CurrentFile, SceneRoot(handle)));
+    } else {
+        // The file has been loaded typed by its `DataType` above. An untyped
+        // load could pick another loader claiming the same extension, e.g.
+        // Bevy's font loader for `.ttf` files instead of the raw loader.
+        commands.spawn((scene_file, CurrentFile));
     }
     Ok(())
 }
+
+/// Removes files from the scene. When the current file is removed, another
+/// scene file becomes the current one.
+fn remove_scene_files(
+    mut event_reader: MessageReader<ui::FileRemoved>,
+    scene_query: Query<(Entity, &SceneFile, Has<CurrentFile>)>,
+    mut commands: Commands,
+    mut file_map: ResMut<file::FileInfoMap>,
+) {
+    let mut removed = Vec::new();
+    let mut current_removed = false;
+    for event in event_reader.read() {
+        for (entity, scene_file, is_current) in scene_query.iter() {
+            if scene_file.comparison == event.comparison
+                && scene_file.path.eq_ignore_ascii_case(&event.file_path)
+            {
+                commands.entity(entity).despawn();
+                if !scene_file.comparison
+                    && let Ok(file_info) = file_map.get_file_mut(&scene_file.path)
+                {
+                    file_info.unload();
+                }
+                removed.push(entity);
+                current_removed |= is_current;
+            }
+        }
+    }
+
+    if current_removed
+        && let Some((entity, ..)) = scene_query
+            .iter()
+            .filter(|(entity, scene_file, _)| !scene_file.comparison && !removed.contains(entity))
+            .last()
+    {
+        commands.entity(entity).insert(CurrentFile);
+    }
+}
+
+/// Indexing of the archives of the game installation being switched to.
+#[derive(Resource)]
+pub struct GameIndexingTask {
+    task: tasks::Task<assets::archive::FileArchiveMap>,
+}
+
+/// Emitted once the archives of a new game installation are indexed and
+/// readable through the [`assets::archive::ArchiveRegistry`].
+#[derive(Message)]
+pub struct GameIndexed;
+
+/// Switches to another game installation: unloads the scene files, and starts
+/// indexing the archives in the background. The file and archive maps are
+/// rebuilt by [`archive::start_loading`] once the indexing is done.
+fn switch_game_path(
+    mut event_reader: MessageReader<ui::GamePathSelected>,
+    entity_query: Query<(Entity, &SceneFile)>,
+    mut commands: Commands,
+    mut settings: ResMut<Settings>,
+    mut file_map: ResMut<file::FileInfoMap>,
+    mut archive_info_map: ResMut<ArchiveInfoMap>,
+    mut load_error_map: ResMut<load_error::LoadErrorMap>,
+) {
+    // Ignore all but the last event
+    let Some(event) = event_reader.read().last() else {
+        return;
+    };
+
+    // Files of the comparison installation are kept
+    for (entity, scene_file) in entity_query.iter().filter(|(_, file)| !file.comparison) {
+        commands.entity(entity).despawn();
+        if let Ok(file_info) = file_map.get_file_mut(&scene_file.path) {
+            file_info.unload();
+        }
+    }
+    // The files of the previous installation cannot be browsed while indexing
+    *file_map = file::FileInfoMap::default();
+    archive_info_map.map.clear();
+    // The same paths may load in the new installation
+    load_error_map.clear();
+    commands.remove_resource::<LoadArchiveTasks>();
+    commands.remove_resource::<references::ScanReferenceTasks>();
+
+    *settings = get_game_settings(&settings, event.game_path.clone());
+    if let Err(err) = settings.save() {
+        warn!("Failed to save settings: {err}");
+    }
+
+    let settings = settings.clone();
+    let task = tasks::IoTaskPool::get()
+        .spawn(async move { assets::archive::FileArchiveMap::new(&settings) });
+    // Replacing a previous task drops it
+    commands.insert_resource(GameIndexingTask { task });
+}
+
+/// Returns the settings to index another game installation with. The locale of
+/// the current installation is reset when the other one does not have it.
+pub fn get_game_settings(settings: &Settings, game_path: String) -> Settings {
+    let mut settings = Settings {
+        game_path,
+        ..settings.clone()
+    };
+    if let Some(locale) = &settings.locale
+        && !assets::archive::get_locales(&settings)
+            .is_ok_and(|locales| locales.iter().any(|l| l.eq_ignore_ascii_case(locale)))
+    {
+        info!(
+            "Locale `{locale}` not found in {}, detecting it",
+            settings.game_path
+        );
+        settings.locale = None;
+    }
+    settings
+}
+
+/// Switches the archive registry to the new game installation once indexed.
+fn check_game_indexing(
+    mut commands: Commands,
+    mut indexing_task: ResMut<GameIndexingTask>,
+    registry: Res<assets::archive::ArchiveRegistry>,
+    mut indexed_writer: MessageWriter<GameIndexed>,
+) {
+    let Some(file_archive_map) = tasks::block_on(tasks::poll_once(&mut indexing_task.task)) else {
+        return;
+    };
+    registry.set(file_archive_map);
+    commands.remove_resource::<GameIndexingTask>();
+    indexed_writer.write(GameIndexed);
+}
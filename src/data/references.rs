@@ -0,0 +1,362 @@
+// Copyright © 2025
+// Author: Nocthir <nocthir@proton.me>
+// SPDX-License-Identifier: MIT or Apache-2.0
+
+//! Cross-reference index of the archive files.
+//!
+//! Models reference their textures, world models their textures and doodad
+//! models, and world maps their textures, models and world models. Every file of
+//! these categories is scanned in the background once the archives are indexed,
+//! so the files referencing a texture or a model can be listed. The references
+//! found in archive files are stored in the archive index cache, so a file is
+//! only scanned again when its archive changes.
+
+use std::collections::{BTreeSet, HashMap};
+use std::io;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use bevy::prelude::*;
+use bevy::tasks::{self, Task};
+use wow_adt as adt;
+use wow_m2 as m2;
+use wow_wmo as wmo;
+
+use crate::assets::{
+    archive::{ArchiveAssetReader, ArchiveRegistry, FileArchiveMap, normalize_path},
+    world_model,
+};
+use crate::data::cache::ArchiveIndexCache;
+use crate::data::file::{FileCategory, FileInfoMap};
+
+#[derive(Resource, Default)]
+pub struct ReferenceIndex {
+    /// Normalized file path → paths of the files it references.
+    references: HashMap<String, Vec<String>>,
+    /// Normalized file path → paths of the files referencing it.
+    referenced_by: HashMap<String, BTreeSet<String>>,
+    pub file_count: usize,
+    pub scanned_count: usize,
+    pub failed_count: usize,
+}
+
+impl ReferenceIndex {
+    pub fn get_references(&self, file_path: &str) -> &[String] {
+        self.references
+            .get(&normalize_path(file_path))
+            .map_or(&[], Vec::as_slice)
+    }
+
+    /// Returns the paths of the files referencing a file, sorted.
+    pub fn get_referenced_by(&self, file_path: &str) -> Vec<&String> {
+        self.referenced_by
+            .get(&normalize_path(file_path))
+            .map_or_else(Vec::new, |paths| paths.iter().collect())
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.scanned_count == self.file_count
+    }
+
+    fn insert(&mut self, file_path: &str, references: Vec<String>) {
+        for reference in &references {
+            self.referenced_by
+                .entry(normalize_path(reference))
+                .or_default()
+                .insert(file_path.to_string());
+        }
+        self.references
+            .insert(normalize_path(file_path), references);
+    }
+
+    /// Records the outcome of the scan of a file, `None` when it failed.
+    fn insert_result(&mut self, file_path: &str, references: Option<Vec<String>>) {
+        self.scanned_count += 1;
+        match references {
+            Some(references) => self.insert(file_path, references),
+            None => self.failed_count += 1,
+        }
+    }
+}
+
+#[derive(Resource)]
+pub struct ScanReferenceTasks {
+    tasks: Vec<Task<Vec<(String, Result<Vec<String>>)>>>,
+    /// File archive map the files are read from.
+    file_archive_map: Arc<FileArchiveMap>,
+    /// Archive → references of its files scanned so far, to be cached.
+    scanned: HashMap<PathBuf, Vec<(String, Option<Vec<String>>)>>,
+}
+
+impl ScanReferenceTasks {
+    /// Number of files scanned by a single task.
+    const BATCH_SIZE: usize = 256;
+}
+
+/// Starts scanning the files of the current game installation, replacing the
+/// index and the tasks of the previous one. Files whose references are cached
+/// are not scanned again.
+pub fn start_scanning(
+    mut commands: Commands,
+    registry: Res<ArchiveRegistry>,
+    file_map: Res<FileInfoMap>,
+) {
+    let file_archive_map = registry.get();
+    let mut file_paths: Vec<String> = file_map
+        .get_files()
+        .filter(|file| is_scanned(&file.path))
+        .map(|file| file.path.clone())
+        .collect();
+    file_paths.sort();
+
+    let mut index = ReferenceIndex {
+        file_count: file_paths.len(),
+        ..default()
+    };
+    file_paths.retain(
+        |file_path| match get_cached_references(&file_archive_map, file_path) {
+            Some(references) => {
+                index.insert_result(file_path, references.clone());
+                false
+            }
+            None => true,
+        },
+    );
+    info!(
+        "Scanning {} files for references, {} cached",
+        file_paths.len(),
+        index.scanned_count
+    );
+
+    let mut tasks = ScanReferenceTasks {
+        tasks: Vec::new(),
+        file_archive_map,
+        scanned: HashMap::new(),
+    };
+    for batch in file_paths.chunks(ScanReferenceTasks::BATCH_SIZE) {
+        let reader = ArchiveAssetReader::new(registry.clone());
+        let batch = batch.to_vec();
+        let task = tasks::AsyncComputeTaskPool::get().spawn(async move {
+            batch
+                .into_iter()
+                .map(|file_path| {
+                    let references = scan_file(&reader, &file_path);
+                    (file_path, references)
+                })
+                .collect()
+        });
+        tasks.tasks.push(task);
+    }
+    commands.insert_resource(index);
+    commands.insert_resource(tasks);
+}
+
+/// Returns the cached references of a file read from an archive.
+fn get_cached_references<'a>(
+    file_archive_map: &'a FileArchiveMap,
+    file_path: &str,
+) -> Option<&'a Option<Vec<String>>> {
+    if file_archive_map.get_overlay_path(file_path).is_some() {
+        return None;
+    }
+    let archive_path = file_archive_map.get_archive_path(file_path).ok()?;
+    file_archive_map
+        .get_archive_index(archive_path)?
+        .references
+        .get(&normalize_path(file_path))
+}
+
+pub fn check_scanning(
+    mut commands: Commands,
+    mut scan_tasks: ResMut<ScanReferenceTasks>,
+    mut index: ResMut<ReferenceIndex>,
+) {
+    // Borrow the fields of the resource separately
+    let scan_tasks = &mut *scan_tasks;
+    let mut tasks = Vec::new();
+    tasks.append(&mut scan_tasks.tasks);
+
+    for mut current_task in tasks {
+        let Some(results) = tasks::block_on(tasks::poll_once(&mut current_task)) else {
+            // Not ready yet, put it back
+            scan_tasks.tasks.push(current_task);
+            continue;
+        };
+        for (file_path, result) in results {
+            let references = result
+                .inspect_err(|err| debug!("Failed to scan {file_path} for references: {err}"))
+                .ok();
+            index.insert_result(&file_path, references.clone());
+            // Loose files of the overlay folder are not cached
+            if scan_tasks
+                .file_archive_map
+                .get_overlay_path(&file_path)
+                .is_none()
+                && let Ok(archive_path) = scan_tasks.file_archive_map.get_archive_path(&file_path)
+            {
+                scan_tasks
+                    .scanned
+                    .entry(archive_path.clone())
+                    .or_default()
+                    .push((normalize_path(&file_path), references));
+            }
+        }
+    }
+
+    if scan_tasks.tasks.is_empty() {
+        info!(
+            "Scanned {} files for references, {} failed",
+            index.scanned_count, index.failed_count
+        );
+        let scanned = std::mem::take(&mut scan_tasks.scanned);
+        if !scanned.is_empty() {
+            let file_archive_map = scan_tasks.file_archive_map.clone();
+            tasks::IoTaskPool::get()
+                .spawn(async move { save_references(&file_archive_map, scanned) })
+                .detach();
+        }
+        commands.remove_resource::<ScanReferenceTasks>();
+    }
+}
+
+/// Stores the references scanned from the files of each archive in the archive
+/// index cache.
+fn save_references(
+    file_archive_map: &FileArchiveMap,
+    scanned: HashMap<PathBuf, Vec<(String, Option<Vec<String>>)>>,
+) {
+    let cache_path = &file_archive_map.cache_path;
+    let mut cache = ArchiveIndexCache::load(cache_path);
+    for (archive_path, references) in scanned {
+        if let Some(index) = file_archive_map.get_archive_index(&archive_path) {
+            cache.insert_references(&archive_path, index, references);
+        }
+    }
+    match cache.save(cache_path) {
+        Ok(()) => info!("Saved the file references in the archive index cache"),
+        Err(err) => warn!("Failed to save the file references: {err}"),
+    }
+}
+
+fn is_scanned(file_path: &str) -> bool {
+    match FileCategory::from_path(file_path) {
+        Some(FileCategory::Model | FileCategory::WorldMap) => true,
+        Some(FileCategory::WorldModel) => world_model::is_world_model_root_path(file_path),
+        _ => false,
+    }
+}
+
+/// Returns the paths of the files referenced by a file, with backslash separators.
+fn scan_file(reader: &ArchiveAssetReader, file_path: &str) -> Result<Vec<String>> {
+    let bytes = reader.read_file(file_path)?;
+    let mut cursor = io::Cursor::new(&bytes);
+    let mut references = Vec::new();
+    match FileCategory::from_path(file_path) {
+        Some(FileCategory::Model) => {
+            let model = m2::M2Model::parse(&mut cursor)?;
+            for texture in &model.textures {
+                // Other texture types are resolved at runtime, e.g. skins
+                if texture.texture_type == m2::chunks::M2TextureType::Hardcoded {
+                    references.push(texture.filename.string.to_string_lossy().to_string());
+                }
+            }
+        }
+        Some(FileCategory::WorldModel) => {
+            let wmo::ParsedWmo::Root(root) = wmo::parse_wmo(&mut cursor)? else {
+                return Err(format!("{file_path} is not a root WMO").into());
+            };
+            references.extend(root.textures.iter().cloned());
+            references.extend(get_chunk_strings(&bytes, b"NDOM"));
+        }
+        Some(FileCategory::WorldMap) => {
+            let world_map = adt::Adt::from_reader(&mut cursor)?;
+            if let Some(mtex) = &world_map.mtex {
+                references.extend(mtex.filenames.iter().cloned());
+            }
+            if let Some(mmdx) = &world_map.mmdx {
+                references.extend(mmdx.filenames.iter().cloned());
+            }
+            if let Some(mwmo) = &world_map.mwmo {
+                references.extend(mwmo.filenames.iter().cloned());
+            }
+        }
+        _ => {}
+    }
+
+    let mut ret: Vec<String> = Vec::new();
+    for reference in references {
+        let reference = fix_model_extension(reference.trim().replace('/', "\\"));
+        if !reference.is_empty() && !ret.iter().any(|path| path.eq_ignore_ascii_case(&reference)) {
+            ret.push(reference);
+        }
+    }
+    Ok(ret)
+}
+
+/// Model paths written with the legacy `.mdx` extension refer to `.m2` files.
+fn fix_model_extension(path: String) -> String {
+    let lowercase_path = path.to_lowercase();
+    if lowercase_path.ends_with(".mdx") || lowercase_path.ends_with(".mdl") {
+        format!("{}.m2", &path[..path.len() - 4])
+    } else {
+        path
+    }
+}
+
+/// Returns the zero terminated strings of the first chunk with the given
+/// magic, as stored in the file (reversed).
+fn get_chunk_strings(bytes: &[u8], magic: &[u8; 4]) -> Vec<String> {
+    let mut offset = 0;
+    while offset + 8 <= bytes.len() {
+        let size = u32::from_le_bytes(bytes[offset + 4..offset + 8].try_into().unwrap()) as usize;
+        let data_start = offset + 8;
+        let data_end = (data_start + size).min(bytes.len());
+        if &bytes[offset..offset + 4] == magic {
+            return bytes[data_start..data_end]
+                .split(|&byte| byte == 0)
+                .filter(|name| !name.is_empty())
+                .map(|name| String::from_utf8_lossy(name).to_string())
+                .collect();
+        }
+        offset = data_end;
+    }
+    Vec::new()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_chunk_strings() {
+        let mut bytes = Vec::new();
+        bytes.extend(b"REVM");
+        bytes.extend(4u32.to_le_bytes());
+        bytes.extend(17u32.to_le_bytes());
+        let names = b"Tree.mdx\0\0Rock.m2\0";
+        bytes.extend(b"NDOM");
+        bytes.extend((names.len() as u32).to_le_bytes());
+        bytes.extend(names);
+
+        assert_eq!(get_chunk_strings(&bytes, b"NDOM"), ["Tree.mdx", "Rock.m2"]);
+        assert!(get_chunk_strings(&bytes, b"XTOM").is_empty());
+        assert_eq!(fix_model_extension("Tree.MDX".into()), "Tree.m2");
+    }
+
+    #[test]
+    fn test_reference_index() {
+        let mut index = ReferenceIndex::default();
+        index.insert("Wolf.m2", vec!["Creature\\Wolf\\Wolf.blp".into()]);
+        index.insert("Worg.m2", vec!["Creature\\Wolf\\Wolf.blp".into()]);
+        assert_eq!(
+            index.get_references("WOLF.M2"),
+            ["Creature\\Wolf\\Wolf.blp"]
+        );
+        index.insert("Worg.m2", vec!["Creature\\Wolf\\Wolf.blp".into()]);
+        assert_eq!(
+            index.get_referenced_by("creature/wolf/wolf.blp"),
+            ["Wolf.m2", "Worg.m2"]
+        );
+        assert!(index.get_referenced_by("Wolf.m2").is_empty());
+    }
+}
@@ -0,0 +1,351 @@
+// Copyright © 2025
+// Author: Nocthir <nocthir@proton.me>
+// SPDX-License-Identifier: MIT or Apache-2.0
+
+//! Batch validation of the asset loaders.
+//!
+//! Every file of the [`FileInfoMap`] is loaded through the `AssetServer` with
+//! the loader of its category, including the dependencies it loads (textures,
+//! world model groups, embedded models), and the outcome is collected in a
+//! [`ValidationReport`]. Comparing the report with one written before bumping
+//! the parser crates shows the files that regressed.
+
+use std::collections::{BTreeMap, VecDeque};
+use std::error::Error;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use bevy::asset::{AssetLoadError, RecursiveDependencyLoadState};
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::assets::archive::{ArchiveRegistry, glob_match};
+use crate::assets::data_base::DataBaseAssetLoaderError;
+use crate::assets::image::ImageLoaderError;
+use crate::assets::model::ModelAssetLoaderError;
+use crate::assets::world_map::WorldMapAssetLoaderError;
+use crate::assets::world_model::{self, WorldModelAssetLoaderError};
+use crate::data::file::FileInfoMap;
+use crate::settings::Settings;
+
+/// Adds the validation systems. The validation starts once the archives are
+/// indexed and is done when [`Validation::is_done`] returns true.
+pub struct ValidationPlugin {
+    /// Glob patterns of the files to validate. Every file is validated when empty.
+    pub patterns: Vec<String>,
+}
+
+impl Plugin for ValidationPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Validation::new(self.patterns.clone()))
+            .init_resource::<FileInfoMap>()
+            .add_systems(Startup, start_validation)
+            .add_systems(Update, validate_files);
+    }
+}
+
+#[derive(Default, Debug, Serialize, Deserialize)]
+pub struct ValidationReport {
+    pub game_path: String,
+    pub file_count: usize,
+    pub success_count: usize,
+    pub failure_count: usize,
+    pub duration_ms: f64,
+    /// Results per file category label.
+    pub categories: BTreeMap<String, CategoryReport>,
+    /// Number of failures per error variant.
+    pub errors: BTreeMap<String, usize>,
+    pub files: Vec<FileReport>,
+}
+
+impl ValidationReport {
+    fn insert(&mut self, file_report: FileReport) {
+        self.file_count += 1;
+        let category = self
+            .categories
+            .entry(file_report.category.clone())
+            .or_default();
+        category.file_count += 1;
+        category.duration_ms += file_report.duration_ms;
+        if let Some(error_kind) = &file_report.error_kind {
+            self.failure_count += 1;
+            category.failure_count += 1;
+            *self.errors.entry(error_kind.clone()).or_default() += 1;
+        } else {
+            self.success_count += 1;
+            category.success_count += 1;
+        }
+        self.files.push(file_report);
+    }
+
+    /// Returns the files that failed in this report but not in `baseline`.
+    pub fn get_regressions(&self, baseline: &ValidationReport) -> Vec<&FileReport> {
+        let baseline_failures: BTreeMap<&str, bool> = baseline
+            .files
+            .iter()
+            .map(|file| (file.path.as_str(), file.is_failure()))
+            .collect();
+        self.files
+            .iter()
+            .filter(|file| {
+                file.is_failure() && baseline_failures.get(file.path.as_str()) == Some(&false)
+            })
+            .collect()
+    }
+}
+
+#[derive(Default, Debug, Serialize, Deserialize)]
+pub struct CategoryReport {
+    pub file_count: usize,
+    pub success_count: usize,
+    pub failure_count: usize,
+    pub duration_ms: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FileReport {
+    pub path: String,
+    pub archive_path: PathBuf,
+    pub category: String,
+    /// Error variant, e.g. `m2::Parse`, prefixed by the extension of the file
+    /// that failed, which may be a dependency of this file.
+    pub error_kind: Option<String>,
+    pub error: Option<String>,
+    /// Time from the load request to the load of the file and its dependencies.
+    pub duration_ms: f64,
+}
+
+impl FileReport {
+    pub fn is_failure(&self) -> bool {
+        self.error_kind.is_some()
+    }
+}
+
+#[derive(Resource)]
+pub struct Validation {
+    patterns: Vec<String>,
+    pending: VecDeque<String>,
+    in_flight: Vec<(String, Instant)>,
+    started: Option<Instant>,
+    pub report: ValidationReport,
+}
+
+impl Validation {
+    /// Maximum number of files loading at the same time.
+    const MAX_IN_FLIGHT: usize = 16;
+    /// Files taking longer than this to load are reported as failed.
+    const LOAD_TIMEOUT: Duration = Duration::from_secs(60);
+
+    fn new(patterns: Vec<String>) -> Self {
+        Self {
+            patterns,
+            pending: VecDeque::new(),
+            in_flight: Vec::new(),
+            started: None,
+            report: ValidationReport::default(),
+        }
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.started.is_some() && self.pending.is_empty() && self.in_flight.is_empty()
+    }
+
+    fn is_selected(&self, file_path: &str) -> bool {
+        self.patterns.is_empty()
+            || self
+                .patterns
+                .iter()
+                .any(|pattern| glob_match(pattern, file_path))
+    }
+}
+
+fn start_validation(
+    settings: Res<Settings>,
+    registry: Res<ArchiveRegistry>,
+    mut file_map: ResMut<FileInfoMap>,
+    mut validation: ResMut<Validation>,
+) -> Result<()> {
+    *file_map = FileInfoMap::new(&registry.get())?;
+
+    let mut file_paths: Vec<String> = file_map
+        .get_files()
+        .filter(|file| {
+            file.data_type
+                .category()
+                .is_some_and(|category| category.class().has_loader)
+        })
+        // Group files are validated through their root file
+        .filter(|file| !world_model::is_world_model_group_path(&file.path))
+        .filter(|file| validation.is_selected(&file.path))
+        .map(|file| file.path.clone())
+        .collect();
+    file_paths.sort();
+    info!("Validating {} files", file_paths.len());
+
+    validation.report.game_path = settings.game_path.clone();
+    validation.pending = file_paths.into();
+    validation.started = Some(Instant::now());
+    Ok(())
+}
+
+fn validate_files(
+    mut validation: ResMut<Validation>,
+    mut file_map: ResMut<FileInfoMap>,
+    mut asset_server: ResMut<AssetServer>,
+) -> Result<()> {
+    let Some(started) = validation.started else {
+        return Ok(());
+    };
+
+    let in_flight = std::mem::take(&mut validation.in_flight);
+    for (file_path, load_started) in in_flight {
+        let file_info = file_map.get_file_mut(&file_path)?;
+        let error = match file_info.get_load_state(&asset_server) {
+            RecursiveDependencyLoadState::Loaded => None,
+            RecursiveDependencyLoadState::Failed(err) => {
+                Some((get_error_kind(&err), err.to_string()))
+            }
+            _ if load_started.elapsed() > Validation::LOAD_TIMEOUT => {
+                Some(("Timeout".into(), "Timed out while loading".into()))
+            }
+            _ => {
+                validation.in_flight.push((file_path, load_started));
+                continue;
+            }
+        };
+        if let Some((_, message)) = &error {
+            warn!("Failed to validate {file_path}: {message}");
+        }
+
+        let category = file_info.data_type.category().unwrap();
+        let (error_kind, error) = error.unzip();
+        validation.report.insert(FileReport {
+            path: file_path,
+            archive_path: file_info.archive_path.clone(),
+            category: category.class().label.into(),
+            error_kind,
+            error,
+            duration_ms: load_started.elapsed().as_secs_f64() * 1000.0,
+        });
+        file_info.unload();
+    }
+
+    while validation.in_flight.len() < Validation::MAX_IN_FLIGHT
+        && let Some(file_path) = validation.pending.pop_front()
+    {
+        file_map.get_file_mut(&file_path)?.load(&mut asset_server);
+        validation.in_flight.push((file_path, Instant::now()));
+    }
+
+    if validation.is_done() {
+        validation.report.duration_ms = started.elapsed().as_secs_f64() * 1000.0;
+    }
+    Ok(())
+}
+
+/// Returns the variant of a load error, prefixed by the extension of the
+/// failing file for loader errors, e.g. `blp::Conversion`.
+fn get_error_kind(err: &AssetLoadError) -> String {
+    match err {
+        AssetLoadError::AssetLoaderError(err) => {
+            let extension = err.path().get_full_extension().unwrap_or_default();
+            format!("{extension}::{}", get_loader_error_kind(err.error()))
+        }
+        AssetLoadError::AssetReaderError { .. } => "AssetReaderError".into(),
+        AssetLoadError::AssetLoaderPanic { .. } => "AssetLoaderPanic".into(),
+        AssetLoadError::MissingLabel { .. } => "MissingLabel".into(),
+        _ => "Other".into(),
+    }
+}
+
+/// Returns the variant of an error of the loaders of this crate, matched on
+/// the error types so renaming a variant cannot go unnoticed.
+fn get_loader_error_kind(err: &(dyn Error + Send + Sync + 'static)) -> &'static str {
+    if let Some(err) = err.downcast_ref::<ImageLoaderError>() {
+        match err {
+            ImageLoaderError::Io(_) => "Io",
+            ImageLoaderError::Parse(_) => "Parse",
+            ImageLoaderError::Conversion(_) => "Conversion",
+            ImageLoaderError::Read(_) => "Read",
+            ImageLoaderError::Size(..) => "Size",
+        }
+    } else if let Some(err) = err.downcast_ref::<ModelAssetLoaderError>() {
+        match err {
+            ModelAssetLoaderError::Io(_) => "Io",
+            ModelAssetLoaderError::Parse(_) => "Parse",
+            ModelAssetLoaderError::Read(_) => "Read",
+            ModelAssetLoaderError::Other(_) => "Other",
+        }
+    } else if let Some(err) = err.downcast_ref::<WorldModelAssetLoaderError>() {
+        match err {
+            WorldModelAssetLoaderError::Io(_) => "Io",
+            WorldModelAssetLoaderError::Parse(_) => "Parse",
+            WorldModelAssetLoaderError::Read(_) => "Read",
+            WorldModelAssetLoaderError::Other(_) => "Other",
+        }
+    } else if let Some(err) = err.downcast_ref::<WorldMapAssetLoaderError>() {
+        match err {
+            WorldMapAssetLoaderError::Io(_) => "Io",
+            WorldMapAssetLoaderError::Parse(_) => "Parse",
+            WorldMapAssetLoaderError::Other(_) => "Other",
+        }
+    } else if let Some(err) = err.downcast_ref::<DataBaseAssetLoaderError>() {
+        match err {
+            DataBaseAssetLoaderError::Io(_) => "Io",
+            DataBaseAssetLoaderError::Parse(_) => "Parse",
+            DataBaseAssetLoaderError::Read(_) => "Read",
+            DataBaseAssetLoaderError::Other(_) => "Other",
+        }
+    } else {
+        "Unknown"
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn file_report(path: &str, error_kind: Option<&str>) -> FileReport {
+        FileReport {
+            path: path.into(),
+            archive_path: PathBuf::new(),
+            category: "Models".into(),
+            error_kind: error_kind.map(Into::into),
+            error: None,
+            duration_ms: 1.0,
+        }
+    }
+
+    #[test]
+    fn test_regressions() {
+        let mut baseline = ValidationReport::default();
+        baseline.insert(file_report("a.m2", None));
+        baseline.insert(file_report("b.m2", Some("m2::Parse")));
+        baseline.insert(file_report("c.m2", None));
+
+        let mut report = ValidationReport::default();
+        report.insert(file_report("a.m2", Some("m2::Parse")));
+        report.insert(file_report("b.m2", Some("m2::Parse")));
+        report.insert(file_report("c.m2", None));
+        report.insert(file_report("d.m2", Some("m2::Io")));
+
+        assert_eq!(report.failure_count, 3);
+        assert_eq!(report.errors["m2::Parse"], 2);
+        assert_eq!(report.categories["Models"].success_count, 1);
+
+        let regressions: Vec<_> = report
+            .get_regressions(&baseline)
+            .iter()
+            .map(|file| file.path.as_str())
+            .collect();
+        assert_eq!(regressions, ["a.m2"]);
+    }
+
+    #[test]
+    fn test_loader_error_kind() {
+        let err = ModelAssetLoaderError::Io(std::io::ErrorKind::NotFound.into());
+        assert_eq!(get_loader_error_kind(&err), "Io");
+        let err = std::io::Error::from(std::io::ErrorKind::NotFound);
+        assert_eq!(get_loader_error_kind(&err), "Unknown");
+    }
+}
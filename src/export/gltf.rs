@@ -0,0 +1,344 @@
+// Copyright © 2025
+// Author: Nocthir <nocthir@proton.me>
+// SPDX-License-Identifier: MIT or Apache-2.0
+
+//! glTF 2.0 writer.
+//!
+//! The geometry and the PNG images share a single binary buffer, stored in a
+//! `.bin` file next to the `.gltf` file, or in the binary chunk of a `.glb`
+//! file. Unlit materials use the `KHR_materials_unlit` extension.
+
+use std::fs;
+use std::path::Path;
+
+use bevy::prelude::*;
+use serde_json::{Value, json};
+
+use super::{ExportMaterial, ExportMesh, ExportNode, ExportScene};
+
+const ARRAY_BUFFER: u32 = 34962;
+const ELEMENT_ARRAY_BUFFER: u32 = 34963;
+const FLOAT: u32 = 5126;
+const UNSIGNED_INT: u32 = 5125;
+const REPEAT: u32 = 10497;
+const LINEAR: u32 = 9729;
+const LINEAR_MIPMAP_LINEAR: u32 = 9987;
+
+const GLB_MAGIC: &[u8; 4] = b"glTF";
+const GLB_VERSION: u32 = 2;
+const JSON_CHUNK: &[u8; 4] = b"JSON";
+const BIN_CHUNK: &[u8; 4] = b"BIN\0";
+const UNLIT_EXTENSION: &str = "KHR_materials_unlit";
+
+/// Writes a `.gltf` file and its `.bin` buffer.
+pub fn write_gltf(scene: &ExportScene, path: &Path) -> Result<()> {
+    let bin_path = path.with_extension("bin");
+    let bin_name = bin_path
+        .file_name()
+        .ok_or("Invalid export path")?
+        .to_string_lossy()
+        .to_string();
+    let (mut document, buffer) = build(scene);
+    document["buffers"][0]["uri"] = json!(bin_name);
+    fs::write(&bin_path, buffer)?;
+    fs::write(path, serde_json::to_vec_pretty(&document)?)?;
+    Ok(())
+}
+
+/// Writes a `.glb` file.
+pub fn write_glb(scene: &ExportScene, path: &Path) -> Result<()> {
+    let (document, buffer) = build(scene);
+    fs::write(path, to_glb(&document, buffer)?)?;
+    Ok(())
+}
+
+/// Packs a document and its buffer in the binary container.
+fn to_glb(document: &Value, mut buffer: Vec<u8>) -> Result<Vec<u8>> {
+    let mut json = serde_json::to_vec(document)?;
+    // Chunks are 4-byte aligned, JSON is padded with spaces
+    pad(&mut json, b' ');
+    pad(&mut buffer, 0);
+
+    let length = 12 + 8 + json.len() + 8 + buffer.len();
+    let mut glb = Vec::with_capacity(length);
+    glb.extend(GLB_MAGIC);
+    glb.extend(GLB_VERSION.to_le_bytes());
+    glb.extend((length as u32).to_le_bytes());
+    glb.extend((json.len() as u32).to_le_bytes());
+    glb.extend(JSON_CHUNK);
+    glb.extend(json);
+    glb.extend((buffer.len() as u32).to_le_bytes());
+    glb.extend(BIN_CHUNK);
+    glb.extend(buffer);
+    Ok(glb)
+}
+
+fn pad(bytes: &mut Vec<u8>, value: u8) {
+    while !bytes.len().is_multiple_of(4) {
+        bytes.push(value);
+    }
+}
+
+/// Builds the glTF document of a scene, and its binary buffer.
+fn build(scene: &ExportScene) -> (Value, Vec<u8>) {
+    let mut builder = Builder::default();
+
+    let nodes: Vec<Value> = scene.nodes.iter().map(node_json).collect();
+    let meshes: Vec<Value> = scene
+        .meshes
+        .iter()
+        .map(|mesh| builder.mesh_json(mesh))
+        .collect();
+    let materials: Vec<Value> = scene.materials.iter().map(material_json).collect();
+    let images: Vec<Value> = scene
+        .images
+        .iter()
+        .map(|image| {
+            let view = builder.push_view(&image.png, None);
+            json!({ "name": image.name, "bufferView": view, "mimeType": "image/png" })
+        })
+        .collect();
+    let textures: Vec<Value> = (0..scene.images.len())
+        .map(|index| json!({ "source": index, "sampler": 0 }))
+        .collect();
+
+    let mut document = json!({
+        "asset": { "version": "2.0", "generator": "worgen-rs" },
+        "scene": 0,
+        "scenes": [{ "name": scene.name, "nodes": scene.roots }],
+        "nodes": nodes,
+        "meshes": meshes,
+        "materials": materials,
+        "samplers": [{
+            "magFilter": LINEAR,
+            "minFilter": LINEAR_MIPMAP_LINEAR,
+            "wrapS": REPEAT,
+            "wrapT": REPEAT,
+        }],
+        "buffers": [{ "byteLength": builder.buffer.len() }],
+        "bufferViews": builder.views,
+        "accessors": builder.accessors,
+    });
+    if !images.is_empty() {
+        document["images"] = json!(images);
+        document["textures"] = json!(textures);
+    }
+    if scene.materials.iter().any(|material| material.unlit) {
+        document["extensionsUsed"] = json!([UNLIT_EXTENSION]);
+    }
+    (document, builder.buffer)
+}
+
+fn node_json(node: &ExportNode) -> Value {
+    let transform = &node.transform;
+    let mut value = json!({
+        "name": node.name,
+        "translation": transform.translation.to_array(),
+        "rotation": transform.rotation.to_array(),
+        "scale": transform.scale.to_array(),
+    });
+    if !node.children.is_empty() {
+        value["children"] = json!(node.children);
+    }
+    if let Some(mesh) = node.mesh {
+        value["mesh"] = json!(mesh);
+    }
+    value
+}
+
+fn material_json(material: &ExportMaterial) -> Value {
+    let mut pbr = json!({
+        "baseColorFactor": material.base_color,
+        "metallicFactor": 0.0,
+        "roughnessFactor": 1.0,
+    });
+    if let Some(texture) = material.base_color_texture {
+        pbr["baseColorTexture"] = json!({ "index": texture });
+    }
+    let mut value = json!({
+        "name": material.name,
+        "pbrMetallicRoughness": pbr,
+        "doubleSided": material.double_sided,
+    });
+    match material.alpha_mode {
+        AlphaMode::Opaque => {}
+        AlphaMode::Mask(cutoff) => {
+            value["alphaMode"] = json!("MASK");
+            value["alphaCutoff"] = json!(cutoff);
+        }
+        // glTF has no additive nor multiplicative blending
+        _ => value["alphaMode"] = json!("BLEND"),
+    }
+    if material.unlit {
+        value["extensions"] = json!({ UNLIT_EXTENSION: {} });
+    }
+    value
+}
+
+/// Binary buffer with its views and accessors.
+#[derive(Default)]
+struct Builder {
+    buffer: Vec<u8>,
+    views: Vec<Value>,
+    accessors: Vec<Value>,
+}
+
+impl Builder {
+    fn mesh_json(&mut self, mesh: &ExportMesh) -> Value {
+        let (min, max) = get_bounds(&mesh.positions);
+        let mut attributes = json!({
+            "POSITION": self.push_floats(&mesh.positions, "VEC3", Some((min, max))),
+        });
+        if !mesh.normals.is_empty() {
+            attributes["NORMAL"] = json!(self.push_floats(&mesh.normals, "VEC3", None));
+        }
+        if !mesh.tex_coords.is_empty() {
+            attributes["TEXCOORD_0"] = json!(self.push_floats(&mesh.tex_coords, "VEC2", None));
+        }
+        if !mesh.colors.is_empty() {
+            attributes["COLOR_0"] = json!(self.push_floats(&mesh.colors, "VEC4", None));
+        }
+
+        let bytes: Vec<u8> = mesh.indices.iter().flat_map(|i| i.to_le_bytes()).collect();
+        let view = self.push_view(&bytes, Some(ELEMENT_ARRAY_BUFFER));
+        let indices = self.push_accessor(json!({
+            "bufferView": view,
+            "componentType": UNSIGNED_INT,
+            "count": mesh.indices.len(),
+            "type": "SCALAR",
+        }));
+
+        json!({
+            "primitives": [{
+                "attributes": attributes,
+                "indices": indices,
+                "material": mesh.material,
+            }],
+        })
+    }
+
+    fn push_floats<const N: usize>(
+        &mut self,
+        values: &[[f32; N]],
+        accessor_type: &str,
+        bounds: Option<([f32; 3], [f32; 3])>,
+    ) -> usize {
+        let bytes: Vec<u8> = values
+            .iter()
+            .flatten()
+            .flat_map(|value| value.to_le_bytes())
+            .collect();
+        let view = self.push_view(&bytes, Some(ARRAY_BUFFER));
+        let mut accessor = json!({
+            "bufferView": view,
+            "componentType": FLOAT,
+            "count": values.len(),
+            "type": accessor_type,
+        });
+        if let Some((min, max)) = bounds {
+            accessor["min"] = json!(min);
+            accessor["max"] = json!(max);
+        }
+        self.push_accessor(accessor)
+    }
+
+    fn push_accessor(&mut self, accessor: Value) -> usize {
+        self.accessors.push(accessor);
+        self.accessors.len() - 1
+    }
+
+    /// Appends bytes to the buffer, 4-byte aligned, returning the view index.
+    fn push_view(&mut self, bytes: &[u8], target: Option<u32>) -> usize {
+        pad(&mut self.buffer, 0);
+        let mut view = json!({
+            "buffer": 0,
+            "byteOffset": self.buffer.len(),
+            "byteLength": bytes.len(),
+        });
+        if let Some(target) = target {
+            view["target"] = json!(target);
+        }
+        self.buffer.extend_from_slice(bytes);
+        self.views.push(view);
+        self.views.len() - 1
+    }
+}
+
+/// Returns the minimum and maximum of the positions, required by glTF.
+fn get_bounds(positions: &[[f32; 3]]) -> ([f32; 3], [f32; 3]) {
+    let mut min = Vec3::splat(f32::MAX);
+    let mut max = Vec3::splat(f32::MIN);
+    for position in positions {
+        min = min.min(Vec3::from_array(*position));
+        max = max.max(Vec3::from_array(*position));
+    }
+    if positions.is_empty() {
+        return ([0.0; 3], [0.0; 3]);
+    }
+    (min.to_array(), max.to_array())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::export::ExportImage;
+
+    fn triangle_scene() -> ExportScene {
+        ExportScene {
+            name: "Triangle".into(),
+            roots: vec![0],
+            nodes: vec![ExportNode {
+                name: "Root".into(),
+                transform: Transform::from_xyz(1.0, 2.0, 3.0),
+                children: Vec::new(),
+                mesh: Some(0),
+            }],
+            meshes: vec![ExportMesh {
+                positions: vec![[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, -1.0]],
+                normals: vec![[0.0, 0.0, 1.0]; 3],
+                indices: vec![0, 1, 2],
+                ..default()
+            }],
+            materials: vec![ExportMaterial {
+                base_color_texture: Some(0),
+                alpha_mode: AlphaMode::Mask(0.5),
+                unlit: true,
+                ..default()
+            }],
+            images: vec![ExportImage {
+                name: "Texture".into(),
+                png: vec![1, 2, 3],
+            }],
+        }
+    }
+
+    #[test]
+    fn test_build() {
+        let (document, buffer) = build(&triangle_scene());
+        assert_eq!(document["nodes"][0]["translation"], json!([1.0, 2.0, 3.0]));
+        assert_eq!(document["accessors"][0]["min"], json!([0.0, 0.0, -1.0]));
+        assert_eq!(document["accessors"][0]["max"], json!([1.0, 1.0, 0.0]));
+        assert_eq!(document["materials"][0]["alphaMode"], "MASK");
+        assert_eq!(document["extensionsUsed"][0], UNLIT_EXTENSION);
+        // Positions, normals, indices and image
+        assert_eq!(buffer.len(), 36 + 36 + 12 + 3);
+        let image_offset = document["bufferViews"][3]["byteOffset"].as_u64().unwrap();
+        assert!(image_offset.is_multiple_of(4));
+        assert_eq!(document["buffers"][0]["byteLength"], buffer.len());
+    }
+
+    #[test]
+    fn test_glb() {
+        let (document, buffer) = build(&triangle_scene());
+        let glb = to_glb(&document, buffer).unwrap();
+        assert_eq!(&glb[0..4], GLB_MAGIC);
+        let length = u32::from_le_bytes(glb[8..12].try_into().unwrap());
+        assert_eq!(length as usize, glb.len());
+        assert!(glb.len().is_multiple_of(4));
+        let json_length = u32::from_le_bytes(glb[12..16].try_into().unwrap()) as usize;
+        assert_eq!(&glb[16..20], JSON_CHUNK);
+        let parsed: Value = serde_json::from_slice(&glb[20..20 + json_length]).unwrap();
+        assert_eq!(parsed["scenes"][0]["name"], "Triangle");
+        assert_eq!(&glb[24 + json_length..28 + json_length], BIN_CHUNK);
+    }
+}
@@ -0,0 +1,593 @@
+// Copyright © 2025
+// Author: Nocthir <nocthir@proton.me>
+// SPDX-License-Identifier: MIT or Apache-2.0
+
+//! Export of the scene files to formats read by other tools.
+//!
+//! The scene of a file is first captured into an [`ExportScene`]: its visible
+//! entities, with their transforms, meshes, materials and images, the models
+//! and world models placed in world maps included. The writers of each format
+//! only work on this capture. Only the capture runs on the main thread: the
+//! images are encoded and the files written by a task of the async compute pool.
+
+pub mod gltf;
+pub mod obj;
+
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+use ::image::RgbaImage;
+use bevy::ecs::system::SystemParam;
+use bevy::mesh::{Indices, VertexAttributeValues};
+use bevy::prelude::*;
+use bevy::render::render_resource::TextureFormat;
+use bevy::tasks::{self, Task};
+
+use crate::assets::{
+    archive::{ArchiveAssetReader, ArchiveRegistry, ComparisonRegistry},
+    image::ImageLoader,
+    material::ExtTerrainMaterial,
+    model::Model,
+    world_model::WorldModel,
+};
+use crate::data::SceneFile;
+
+/// Folder the exported files are written to.
+pub const EXPORT_PATH: &str = "export";
+
+pub struct ExportPlugin;
+
+impl Plugin for ExportPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_message::<ExportRequested>()
+            .init_resource::<ExportStatus>()
+            .init_resource::<ExportTasks>()
+            .add_systems(
+                Update,
+                (
+                    export_scene_files,
+                    check_exports.run_if(|tasks: Res<ExportTasks>| !tasks.is_empty()),
+                )
+                    .chain(),
+            );
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// glTF JSON, with the geometry and the PNG images in a `.bin` file.
+    Gltf,
+    /// Binary glTF, a single file.
+    Glb,
+    /// Wavefront OBJ, with its materials in a `.mtl` file and PNG images.
+    Obj,
+}
+
+impl ExportFormat {
+    pub const ALL: [ExportFormat; 3] = [ExportFormat::Gltf, ExportFormat::Glb, ExportFormat::Obj];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ExportFormat::Gltf => "glTF",
+            ExportFormat::Glb => "GLB",
+            ExportFormat::Obj => "OBJ",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Gltf => "gltf",
+            ExportFormat::Glb => "glb",
+            ExportFormat::Obj => "obj",
+        }
+    }
+}
+
+/// Requests exporting a scene file entity.
+#[derive(Message)]
+pub struct ExportRequested {
+    pub entity: Entity,
+    pub format: ExportFormat,
+}
+
+impl ExportRequested {
+    pub fn new(entity: Entity, format: ExportFormat) -> Self {
+        info!("Export requested: {} ({entity})", format.name());
+        Self { entity, format }
+    }
+}
+
+/// Outcome of the last export, shown in the right panel.
+#[derive(Resource, Default)]
+pub struct ExportStatus {
+    /// Path of the exported file, or why the export failed.
+    pub last: Option<std::result::Result<PathBuf, String>>,
+}
+
+/// Exports being encoded and written in the background, with the path of
+/// their scene file.
+#[derive(Resource, Default)]
+pub struct ExportTasks {
+    tasks: Vec<(String, Task<Result<PathBuf>>)>,
+}
+
+impl ExportTasks {
+    pub fn is_empty(&self) -> bool {
+        self.tasks.is_empty()
+    }
+}
+
+/// Captures the scene files of the export requests, and spawns the tasks
+/// writing them.
+fn export_scene_files(
+    mut event_reader: MessageReader<ExportRequested>,
+    q_files: Query<&SceneFile>,
+    source: ExportSource,
+    mut status: ResMut<ExportStatus>,
+    mut export_tasks: ResMut<ExportTasks>,
+) {
+    for event in event_reader.read() {
+        let Ok(scene_file) = q_files.get(event.entity) else {
+            continue;
+        };
+        let captured = source.capture(event.entity, scene_file);
+        if captured.scene.meshes.is_empty() {
+            let err = format!("{} has no mesh to export", scene_file.path);
+            error!("Failed to export {}: {err}", scene_file.path);
+            status.last = Some(Err(err));
+            continue;
+        }
+
+        let path = get_output_path(scene_file, event.format);
+        let format = event.format;
+        let task = tasks::AsyncComputeTaskPool::get()
+            .spawn(async move { write_scene(captured, format, path) });
+        export_tasks.tasks.push((scene_file.path.clone(), task));
+    }
+}
+
+/// Records the outcome of the finished exports.
+fn check_exports(mut export_tasks: ResMut<ExportTasks>, mut status: ResMut<ExportStatus>) {
+    export_tasks.tasks.retain_mut(|(file_path, task)| {
+        let Some(result) = tasks::block_on(tasks::poll_once(task)) else {
+            return true;
+        };
+        match &result {
+            Ok(path) => info!("Exported {file_path} to {}", path.display()),
+            Err(err) => error!("Failed to export {file_path}: {err}"),
+        }
+        status.last = Some(result.map_err(|err| err.to_string()));
+        false
+    });
+}
+
+/// Returns the path of the exported file, in the same folders as the scene
+/// file in the archives so files with the same name do not overwrite each
+/// other. Files of the comparison game installation go to a `comparison`
+/// folder.
+fn get_output_path(scene_file: &SceneFile, format: ExportFormat) -> PathBuf {
+    let mut path = PathBuf::from(EXPORT_PATH);
+    if scene_file.comparison {
+        path.push("comparison");
+    }
+    for part in scene_file.path.split(['\\', '/']) {
+        // Only keep plain names, so the file stays in the export folder
+        if !part.is_empty() && part != "." && part != ".." && !part.contains(':') {
+            path.push(part);
+        }
+    }
+    path.set_extension(format.extension());
+    path
+}
+
+/// Encodes the images of a captured scene and writes it.
+fn write_scene(captured: CapturedScene, format: ExportFormat, path: PathBuf) -> Result<PathBuf> {
+    let scene = captured.encode_images();
+    fs::create_dir_all(path.parent().ok_or("Invalid export path")?)?;
+    match format {
+        ExportFormat::Gltf => gltf::write_gltf(&scene, &path)?,
+        ExportFormat::Glb => gltf::write_glb(&scene, &path)?,
+        ExportFormat::Obj => obj::write_obj(&scene, &path)?,
+    }
+    Ok(path)
+}
+
+/// Scene of a file, as captured for the exporters.
+#[derive(Default)]
+pub struct ExportScene {
+    /// File name without extension, used to name the exported files.
+    pub name: String,
+    /// Indices of the nodes without parent.
+    pub roots: Vec<usize>,
+    pub nodes: Vec<ExportNode>,
+    pub meshes: Vec<ExportMesh>,
+    pub materials: Vec<ExportMaterial>,
+    pub images: Vec<ExportImage>,
+}
+
+pub struct ExportNode {
+    pub name: String,
+    /// Transform relative to the parent node.
+    pub transform: Transform,
+    pub children: Vec<usize>,
+    pub mesh: Option<usize>,
+}
+
+/// Triangle list with a single material.
+#[derive(Default)]
+pub struct ExportMesh {
+    pub positions: Vec<[f32; 3]>,
+    pub normals: Vec<[f32; 3]>,
+    pub tex_coords: Vec<[f32; 2]>,
+    pub colors: Vec<[f32; 4]>,
+    pub indices: Vec<u32>,
+    pub material: usize,
+}
+
+pub struct ExportMaterial {
+    pub name: String,
+    /// Linear RGBA.
+    pub base_color: [f32; 4],
+    pub base_color_texture: Option<usize>,
+    pub alpha_mode: AlphaMode,
+    pub double_sided: bool,
+    pub unlit: bool,
+}
+
+impl Default for ExportMaterial {
+    fn default() -> Self {
+        Self {
+            name: "default".into(),
+            base_color: [1.0; 4],
+            base_color_texture: None,
+            alpha_mode: AlphaMode::Opaque,
+            double_sided: false,
+            unlit: false,
+        }
+    }
+}
+
+pub struct ExportImage {
+    /// File name without extension, unique in the scene.
+    pub name: String,
+    pub png: Vec<u8>,
+}
+
+/// Scene captured on the main thread, whose images are not encoded yet.
+pub struct CapturedScene {
+    /// Scene without images.
+    pub scene: ExportScene,
+    images: Vec<CapturedImage>,
+    /// Registry of the game installation the scene file is read from.
+    registry: ArchiveRegistry,
+}
+
+impl CapturedScene {
+    /// Encodes the images to PNG. The images failing to encode are left out,
+    /// and the materials using them lose their texture.
+    fn encode_images(self) -> ExportScene {
+        let CapturedScene {
+            mut scene,
+            images,
+            registry,
+        } = self;
+        let reader = ArchiveAssetReader::new(registry);
+        let mut indices = Vec::with_capacity(images.len());
+        for image in images {
+            let index = match image.encode_png(&reader) {
+                Ok(png) => {
+                    scene.images.push(ExportImage {
+                        name: image.name,
+                        png,
+                    });
+                    Some(scene.images.len() - 1)
+                }
+                Err(err) => {
+                    warn!("Failed to export image {}: {err}", image.name);
+                    None
+                }
+            };
+            indices.push(index);
+        }
+        for material in &mut scene.materials {
+            material.base_color_texture =
+                material.base_color_texture.and_then(|index| indices[index]);
+        }
+        scene
+    }
+}
+
+struct CapturedImage {
+    /// File name without extension, unique in the scene.
+    name: String,
+    source: ImageSource,
+}
+
+enum ImageSource {
+    /// Pixels of the first mip level.
+    Rgba(RgbaImage),
+    /// Path of a block-compressed image. Its pixels are not available on the
+    /// CPU, so its file is read and decoded again.
+    File(PathBuf),
+}
+
+impl CapturedImage {
+    fn encode_png(&self, reader: &ArchiveAssetReader) -> Result<Vec<u8>> {
+        match &self.source {
+            ImageSource::Rgba(rgba) => encode_png(rgba),
+            ImageSource::File(path) => {
+                let bytes = reader.read_file(path)?;
+                encode_png(&get_rgba(&ImageLoader::decode_bytes(&bytes)?)?)
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+enum MaterialId {
+    Standard(AssetId<StandardMaterial>),
+    Terrain(AssetId<ExtTerrainMaterial>),
+}
+
+type NodeQuery<'w, 's> = Query<
+    'w,
+    's,
+    (
+        &'static Transform,
+        Option<&'static Name>,
+        Option<&'static Model>,
+        Option<&'static WorldModel>,
+        Option<&'static Visibility>,
+        Option<&'static Children>,
+        Option<&'static Mesh3d>,
+        Option<&'static MeshMaterial3d<StandardMaterial>>,
+        Option<&'static MeshMaterial3d<ExtTerrainMaterial>>,
+    ),
+>;
+
+/// Everything needed to capture the scene of a file.
+#[derive(SystemParam)]
+pub struct ExportSource<'w, 's> {
+    q_nodes: NodeQuery<'w, 's>,
+    q_children: Query<'w, 's, &'static Children>,
+    asset_server: Res<'w, AssetServer>,
+    meshes: Res<'w, Assets<Mesh>>,
+    materials: Res<'w, Assets<StandardMaterial>>,
+    terrain_materials: Res<'w, Assets<ExtTerrainMaterial>>,
+    images: Res<'w, Assets<Image>>,
+    registry: Res<'w, ArchiveRegistry>,
+    comparison_registry: Res<'w, ComparisonRegistry>,
+}
+
+/// Indices of the meshes, materials and images already captured.
+#[derive(Default)]
+struct CaptureState {
+    scene: ExportScene,
+    meshes: HashMap<(AssetId<Mesh>, Option<MaterialId>), usize>,
+    materials: HashMap<Option<MaterialId>, usize>,
+    images: HashMap<AssetId<Image>, Option<usize>>,
+    captured_images: Vec<CapturedImage>,
+}
+
+impl ExportSource<'_, '_> {
+    /// Captures the scene spawned under a scene file entity. The transform of
+    /// the scene file itself, its placement in the viewer, is left out.
+    pub fn capture(&self, file_entity: Entity, scene_file: &SceneFile) -> CapturedScene {
+        let file_path = scene_file.path.as_str();
+        let file_name = file_path.rsplit('\\').next().unwrap_or(file_path);
+        let name = file_name
+            .rsplit_once('.')
+            .map_or(file_name, |(stem, _)| stem);
+
+        let mut state = CaptureState::default();
+        state.scene.name = name.to_string();
+        for child in self.q_children.get(file_entity).into_iter().flatten() {
+            if let Some(index) = self.capture_node(*child, &mut state) {
+                state.scene.roots.push(index);
+            }
+        }
+        let registry = if scene_file.comparison {
+            self.comparison_registry.0.clone()
+        } else {
+            self.registry.clone()
+        };
+        CapturedScene {
+            scene: state.scene,
+            images: state.captured_images,
+            registry,
+        }
+    }
+
+    /// Captures an entity and its descendants, returning the index of its
+    /// node. Hidden entities, e.g. unselected geosets, are skipped.
+    fn capture_node(&self, entity: Entity, state: &mut CaptureState) -> Option<usize> {
+        let (
+            transform,
+            name,
+            model,
+            world_model,
+            visibility,
+            children,
+            mesh,
+            material,
+            terrain_material,
+        ) = self.q_nodes.get(entity).ok()?;
+        if visibility == Some(&Visibility::Hidden) {
+            return None;
+        }
+
+        let index = state.scene.nodes.len();
+        let name = name
+            .map(|name| name.to_string())
+            .or_else(|| model.map(|model| model.name.clone()))
+            .or_else(|| world_model.map(|world_model| world_model.name.clone()))
+            .unwrap_or_else(|| format!("node_{index}"));
+        state.scene.nodes.push(ExportNode {
+            name,
+            transform: *transform,
+            children: Vec::new(),
+            mesh: None,
+        });
+
+        if let Some(mesh) = mesh {
+            let material_id = material
+                .map(|material| MaterialId::Standard(material.id()))
+                .or_else(|| terrain_material.map(|material| MaterialId::Terrain(material.id())));
+            state.scene.nodes[index].mesh = self.capture_mesh(mesh.id(), material_id, state);
+        }
+
+        for child in children.into_iter().flatten() {
+            if let Some(child_index) = self.capture_node(*child, state) {
+                state.scene.nodes[index].children.push(child_index);
+            }
+        }
+        Some(index)
+    }
+
+    fn capture_mesh(
+        &self,
+        mesh_id: AssetId<Mesh>,
+        material_id: Option<MaterialId>,
+        state: &mut CaptureState,
+    ) -> Option<usize> {
+        if let Some(index) = state.meshes.get(&(mesh_id, material_id)) {
+            return Some(*index);
+        }
+        let mesh = self.meshes.get(mesh_id)?;
+        let positions = mesh
+            .attribute(Mesh::ATTRIBUTE_POSITION)?
+            .as_float3()?
+            .to_vec();
+        let normals = mesh
+            .attribute(Mesh::ATTRIBUTE_NORMAL)
+            .and_then(VertexAttributeValues::as_float3)
+            .map(<[_]>::to_vec)
+            .unwrap_or_default();
+        let tex_coords = match mesh.attribute(Mesh::ATTRIBUTE_UV_0) {
+            Some(VertexAttributeValues::Float32x2(tex_coords)) => tex_coords.clone(),
+            _ => Vec::new(),
+        };
+        let colors = match mesh.attribute(Mesh::ATTRIBUTE_COLOR) {
+            Some(VertexAttributeValues::Float32x4(colors)) => colors.clone(),
+            _ => Vec::new(),
+        };
+        let indices = match mesh.indices() {
+            Some(Indices::U16(indices)) => indices.iter().map(|&i| i as u32).collect(),
+            Some(Indices::U32(indices)) => indices.clone(),
+            None => (0..positions.len() as u32).collect(),
+        };
+        let material = self.capture_material(material_id, state);
+
+        let index = state.scene.meshes.len();
+        state.scene.meshes.push(ExportMesh {
+            positions,
+            normals,
+            tex_coords,
+            colors,
+            indices,
+            material,
+        });
+        state.meshes.insert((mesh_id, material_id), index);
+        Some(index)
+    }
+
+    fn capture_material(&self, material_id: Option<MaterialId>, state: &mut CaptureState) -> usize {
+        if let Some(index) = state.materials.get(&material_id) {
+            return *index;
+        }
+        // Terrains are exported with their base layer only
+        let material = match material_id {
+            Some(MaterialId::Standard(id)) => self.materials.get(id),
+            Some(MaterialId::Terrain(id)) => self.terrain_materials.get(id).map(|m| &m.base),
+            None => None,
+        };
+
+        let index = state.scene.materials.len();
+        let mut export_material = ExportMaterial {
+            name: format!("material_{index}"),
+            ..default()
+        };
+        if let Some(material) = material {
+            export_material.base_color = material.base_color.to_linear().to_f32_array();
+            export_material.base_color_texture = material
+                .base_color_texture
+                .as_ref()
+                .and_then(|image| self.capture_image(image.id(), state));
+            export_material.alpha_mode = material.alpha_mode;
+            export_material.double_sided = material.cull_mode.is_none();
+            export_material.unlit = material.unlit;
+        }
+        state.scene.materials.push(export_material);
+        state.materials.insert(material_id, index);
+        index
+    }
+
+    fn capture_image(&self, image_id: AssetId<Image>, state: &mut CaptureState) -> Option<usize> {
+        if let Some(index) = state.images.get(&image_id) {
+            return *index;
+        }
+        let index = self.images.get(image_id).and_then(|image| {
+            let source = self
+                .get_image_source(image_id, image)
+                .inspect_err(|err| warn!("Failed to export image {image_id}: {err}"))
+                .ok()?;
+            let index = state.captured_images.len();
+            let name = self
+                .asset_server
+                .get_path(image_id)
+                .and_then(|path| {
+                    let stem = path.path().file_stem()?.to_string_lossy().to_string();
+                    Some(stem)
+                })
+                .filter(|stem| !state.captured_images.iter().any(|i| i.name == *stem))
+                .unwrap_or_else(|| format!("image_{index}"));
+            state.captured_images.push(CapturedImage { name, source });
+            Some(index)
+        });
+        state.images.insert(image_id, index);
+        index
+    }
+
+    /// Copies the pixels of an image, or keeps the path of a block-compressed
+    /// image to decode its file again.
+    fn get_image_source(&self, image_id: AssetId<Image>, image: &Image) -> Result<ImageSource> {
+        if !image.texture_descriptor.format.is_compressed() {
+            return Ok(ImageSource::Rgba(get_rgba(image)?));
+        }
+        let path = self
+            .asset_server
+            .get_path(image_id)
+            .ok_or("Image path not available")?;
+        Ok(ImageSource::File(path.path().to_path_buf()))
+    }
+}
+
+/// Encodes an image to PNG.
+fn encode_png(rgba: &RgbaImage) -> Result<Vec<u8>> {
+    let mut png = io::Cursor::new(Vec::new());
+    rgba.write_to(&mut png, ::image::ImageFormat::Png)?;
+    Ok(png.into_inner())
+}
+
+/// Returns the first mip level of an RGBA image, as decoded by the image loader.
+fn get_rgba(image: &Image) -> Result<RgbaImage> {
+    let format = image.texture_descriptor.format;
+    if !matches!(
+        format,
+        TextureFormat::Rgba8Unorm | TextureFormat::Rgba8UnormSrgb
+    ) {
+        return Err(format!("Unsupported texture format {format:?}").into());
+    }
+    // The first mip level comes first
+    let size = (image.width() * image.height() * 4) as usize;
+    let data = image
+        .data
+        .as_ref()
+        .and_then(|data| data.get(..size))
+        .ok_or("Image data not available")?;
+    let rgba = RgbaImage::from_raw(image.width(), image.height(), data.to_vec())
+        .ok_or("Invalid image size")?;
+    Ok(rgba)
+}
@@ -0,0 +1,208 @@
+// Copyright © 2025
+// Author: Nocthir <nocthir@proton.me>
+// SPDX-License-Identifier: MIT or Apache-2.0
+
+//! Wavefront OBJ writer.
+//!
+//! OBJ has no hierarchy, so every mesh is written as an object with the
+//! vertices moved by the transforms of its node and its ancestors, the same
+//! placement as in the viewer. Materials are written to a `.mtl` file next to
+//! the `.obj` file, and the images to PNG files next to both, prefixed by the
+//! name of the `.obj` file so the images of several exports do not overwrite
+//! each other.
+
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+
+use bevy::math::{Affine3A, Vec3A};
+use bevy::prelude::*;
+
+use super::{ExportMesh, ExportScene};
+
+/// Writes the `.obj` file, its `.mtl` file and the PNG images.
+pub fn write_obj(scene: &ExportScene, path: &Path) -> Result<()> {
+    let mtl_path = path.with_extension("mtl");
+    let mtl_name = mtl_path
+        .file_name()
+        .ok_or("Invalid export path")?
+        .to_string_lossy()
+        .to_string();
+    let folder = path.parent().ok_or("Invalid export path")?;
+    let image_prefix = path
+        .file_stem()
+        .ok_or("Invalid export path")?
+        .to_string_lossy();
+    for image in &scene.images {
+        fs::write(
+            folder.join(get_image_file_name(&image_prefix, &image.name)),
+            &image.png,
+        )?;
+    }
+    fs::write(&mtl_path, to_mtl(scene, &image_prefix))?;
+    fs::write(path, to_obj(scene, &mtl_name))?;
+    Ok(())
+}
+
+fn to_obj(scene: &ExportScene, mtl_name: &str) -> String {
+    let mut obj = String::new();
+    writeln!(obj, "# {}", scene.name).unwrap();
+    writeln!(obj, "mtllib {mtl_name}").unwrap();
+
+    // OBJ indices start at 1 and are shared by all the objects
+    let mut offset = 1;
+    let mut nodes: Vec<(usize, Affine3A)> = scene
+        .roots
+        .iter()
+        .map(|&root| (root, Affine3A::IDENTITY))
+        .collect();
+    while let Some((index, parent_affine)) = nodes.pop() {
+        let node = &scene.nodes[index];
+        let affine = parent_affine * node.transform.compute_affine();
+        nodes.extend(node.children.iter().map(|&child| (child, affine)));
+
+        let Some(mesh) = node.mesh.map(|mesh| &scene.meshes[mesh]) else {
+            continue;
+        };
+        writeln!(obj, "o {}", node.name.replace(char::is_whitespace, "_")).unwrap();
+        writeln!(obj, "usemtl {}", scene.materials[mesh.material].name).unwrap();
+        write_mesh(&mut obj, mesh, affine, offset);
+        offset += mesh.positions.len();
+    }
+    obj
+}
+
+fn write_mesh(obj: &mut String, mesh: &ExportMesh, affine: Affine3A, offset: usize) {
+    for position in &mesh.positions {
+        let [x, y, z] = affine
+            .transform_point3(Vec3::from_array(*position))
+            .to_array();
+        writeln!(obj, "v {x} {y} {z}").unwrap();
+    }
+    // UVs have their origin at the bottom left in OBJ
+    for [u, v] in &mesh.tex_coords {
+        writeln!(obj, "vt {u} {}", 1.0 - v).unwrap();
+    }
+    let normal_matrix = affine.matrix3.inverse().transpose();
+    for normal in &mesh.normals {
+        let normal = (normal_matrix * Vec3A::from_array(*normal)).normalize_or_zero();
+        let [x, y, z] = normal.to_array();
+        writeln!(obj, "vn {x} {y} {z}").unwrap();
+    }
+
+    // A mirroring transform reverses the winding order
+    let mirrored = affine.matrix3.determinant() < 0.0;
+    let has_tex_coords = !mesh.tex_coords.is_empty();
+    let has_normals = !mesh.normals.is_empty();
+    for triangle in mesh.indices.chunks_exact(3) {
+        let mut triangle = [triangle[0], triangle[1], triangle[2]];
+        if mirrored {
+            triangle.swap(1, 2);
+        }
+        obj.push('f');
+        for index in triangle {
+            let index = index as usize + offset;
+            match (has_tex_coords, has_normals) {
+                (true, true) => write!(obj, " {index}/{index}/{index}"),
+                (true, false) => write!(obj, " {index}/{index}"),
+                (false, true) => write!(obj, " {index}//{index}"),
+                (false, false) => write!(obj, " {index}"),
+            }
+            .unwrap();
+        }
+        obj.push('\n');
+    }
+}
+
+fn get_image_file_name(image_prefix: &str, image_name: &str) -> String {
+    format!("{image_prefix}_{image_name}.png")
+}
+
+fn to_mtl(scene: &ExportScene, image_prefix: &str) -> String {
+    let mut mtl = String::new();
+    for material in &scene.materials {
+        let [r, g, b, a] = material.base_color;
+        writeln!(mtl, "newmtl {}", material.name).unwrap();
+        writeln!(mtl, "Kd {r} {g} {b}").unwrap();
+        if material.alpha_mode != AlphaMode::Opaque {
+            writeln!(mtl, "d {a}").unwrap();
+        }
+        // 0: color only, 1: diffuse lighting
+        writeln!(mtl, "illum {}", if material.unlit { 0 } else { 1 }).unwrap();
+        if let Some(image) = material.base_color_texture {
+            let file_name = get_image_file_name(image_prefix, &scene.images[image].name);
+            writeln!(mtl, "map_Kd {file_name}").unwrap();
+            if material.alpha_mode != AlphaMode::Opaque {
+                writeln!(mtl, "map_d {file_name}").unwrap();
+            }
+        }
+        mtl.push('\n');
+    }
+    mtl
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::export::{ExportImage, ExportMaterial, ExportNode};
+
+    #[test]
+    fn test_obj() {
+        let scene = ExportScene {
+            name: "Quad".into(),
+            roots: vec![0],
+            nodes: vec![
+                ExportNode {
+                    name: "Root".into(),
+                    transform: Transform::from_xyz(0.0, 1.0, 0.0),
+                    children: vec![1],
+                    mesh: None,
+                },
+                ExportNode {
+                    name: "Mirrored quad".into(),
+                    transform: Transform::from_scale(vec3(-1.0, 1.0, 1.0)),
+                    children: Vec::new(),
+                    mesh: Some(0),
+                },
+            ],
+            meshes: vec![ExportMesh {
+                positions: vec![[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]],
+                tex_coords: vec![[0.0, 0.0], [1.0, 0.0], [0.0, 0.25]],
+                indices: vec![0, 1, 2],
+                ..default()
+            }],
+            materials: vec![ExportMaterial {
+                base_color_texture: Some(0),
+                ..default()
+            }],
+            images: vec![ExportImage {
+                name: "Texture".into(),
+                png: Vec::new(),
+            }],
+        };
+
+        let obj = to_obj(&scene, "Quad.mtl");
+        let lines: Vec<&str> = obj.lines().collect();
+        assert_eq!(
+            lines,
+            [
+                "# Quad",
+                "mtllib Quad.mtl",
+                "o Mirrored_quad",
+                "usemtl default",
+                "v -1 1 0",
+                "v 0 2 0",
+                "v 0 1 1",
+                "vt 0 1",
+                "vt 1 1",
+                "vt 0 0.75",
+                "f 1/1 3/3 2/2",
+            ]
+        );
+
+        let mtl = to_mtl(&scene, "Quad");
+        assert!(mtl.contains("newmtl default\n"));
+        assert!(mtl.contains("map_Kd Quad_Texture.png\n"));
+        assert!(!mtl.contains("map_d"));
+    }
+}
@@ -6,6 +6,8 @@
 
 pub mod assets;
 pub mod camera;
+pub mod comparison;
 pub mod data;
+pub mod export;
 pub mod settings;
 pub mod ui;
@@ -7,9 +7,10 @@ use bevy::{asset::AssetMetaCheck, diagnostic, prelude::*};
 use worgen_rs::*;
 
 fn main() {
-    settings::Settings::init();
+    let settings = settings::Settings::load().expect("Failed to load settings");
 
     App::new()
+        .insert_resource(settings)
         .add_plugins((
             assets::archive::ArchiveAssetReaderPlugin,
             DefaultPlugins.set(AssetPlugin {
@@ -25,5 +26,7 @@ fn main() {
         .add_plugins(ui::UiPlugin)
         .add_plugins(data::DataPlugin)
         .add_plugins(camera::PanOrbitCameraPlugin)
+        .add_plugins(comparison::ComparisonPlugin)
+        .add_plugins(export::ExportPlugin)
         .run();
 }
@@ -2,7 +2,8 @@
 // Author: Nocthir <nocthir@proton.me>
 // SPDX-License-Identifier: MIT or Apache-2.0
 
-use std::{fs, io, ptr::addr_of, sync::Once};
+use std::path::Path;
+use std::{fs, io};
 
 use anyhow::Result;
 use bevy::prelude::*;
@@ -32,42 +33,46 @@ fn apply_terrain_settings(
     }
 }
 
-pub static mut SETTINGS: Settings = Settings::new();
-static SETTINGS_ONCE: Once = Once::new();
+/// Default location of the settings file, used by the viewer.
+pub const SETTINGS_PATH: &str = "assets/settings.json";
 
-#[derive(Resource, Default, Serialize, Deserialize)]
+#[derive(Resource, Default, Clone, Serialize, Deserialize)]
 pub struct Settings {
     pub game_path: String,
     pub test_image_path: String,
     pub test_model_path: Option<String>,
+    /// Locale folder to load archives from, e.g. `enUS`. The first locale folder
+    /// found in the game data folder is used when not set or not found.
+    pub locale: Option<String>,
+    /// Folder of loose files overriding the archive contents. Files are matched
+    /// by their path relative to this folder, ignoring case.
+    pub overlay_path: Option<String>,
+    /// External listfiles, one file name per line, used to name the files of
+    /// archives without a complete internal `(listfile)`.
+    #[serde(default)]
+    pub listfile_paths: Vec<String>,
+    /// Game installation whose files can be compared with the ones of the
+    /// current installation, see [`crate::comparison`].
+    #[serde(default)]
+    pub comparison_game_path: Option<String>,
 }
 
 impl Settings {
-    pub const fn new() -> Self {
-        Self {
-            game_path: String::new(),
-            test_image_path: String::new(),
-            test_model_path: None,
-        }
-    }
-
-    pub fn get() -> &'static Self {
-        debug_assert!(SETTINGS_ONCE.is_completed());
-        // SAFETY: no mut references exist at this point
-        unsafe { &*addr_of!(SETTINGS) }
+    pub fn load() -> Result<Self> {
+        Self::load_from(SETTINGS_PATH)
     }
 
-    pub fn load(&mut self) -> Result<()> {
-        let file = fs::read("assets/settings.json")?;
+    pub fn load_from<P: AsRef<Path>>(settings_path: P) -> Result<Self> {
+        let file = fs::read(settings_path)?;
         let reader = io::Cursor::new(file);
-        *self = serde_json::from_reader(reader)?;
-        Ok(())
+        let settings: Settings = serde_json::from_reader(reader)?;
+        Ok(settings)
     }
 
-    pub fn init() {
-        // SAFETY: no concurrent static mut access due to std::Once
-        #[allow(static_mut_refs)]
-        SETTINGS_ONCE.call_once(|| unsafe { SETTINGS.load().expect("Failed to load settings") });
+    pub fn save(&self) -> Result<()> {
+        let file = serde_json::to_vec_pretty(self)?;
+        fs::write(SETTINGS_PATH, file)?;
+        Ok(())
     }
 }
 
@@ -0,0 +1,228 @@
+// Copyright © 2025
+// Author: Nocthir <nocthir@proton.me>
+// SPDX-License-Identifier: MIT or Apache-2.0
+
+//! Favorite and recently opened files, persisted across sessions, and the
+//! back/forward navigation history of the selected files.
+
+use std::fs;
+
+use bevy::prelude::*;
+use bevy_egui::EguiContexts;
+use serde::{Deserialize, Serialize};
+
+use crate::ui::FileSelected;
+
+const BOOKMARKS_PATH: &str = "assets/bookmarks.json";
+
+#[derive(Resource, Default, Serialize, Deserialize)]
+pub struct Bookmarks {
+    #[serde(default)]
+    pub favorites: Vec<String>,
+    /// Most recently opened first.
+    #[serde(default)]
+    pub recent: Vec<String>,
+}
+
+impl Bookmarks {
+    const MAX_RECENT: usize = 20;
+
+    /// Loads the bookmarks file, returning empty bookmarks when it is missing.
+    pub fn load() -> Self {
+        match Self::read() {
+            Ok(bookmarks) => bookmarks,
+            Err(err) => {
+                info!("No bookmarks available: {err}");
+                Self::default()
+            }
+        }
+    }
+
+    fn read() -> Result<Self> {
+        let file = fs::read(BOOKMARKS_PATH)?;
+        Ok(serde_json::from_slice(&file)?)
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let file = serde_json::to_vec_pretty(self)?;
+        fs::write(BOOKMARKS_PATH, file)?;
+        Ok(())
+    }
+
+    pub fn is_favorite(&self, file_path: &str) -> bool {
+        self.favorites
+            .iter()
+            .any(|path| path.eq_ignore_ascii_case(file_path))
+    }
+
+    pub fn toggle_favorite(&mut self, file_path: &str) {
+        if self.is_favorite(file_path) {
+            self.favorites
+                .retain(|path| !path.eq_ignore_ascii_case(file_path));
+        } else {
+            self.favorites.push(file_path.to_string());
+        }
+    }
+
+    /// Moves the file to the top of the recent files.
+    pub fn add_recent(&mut self, file_path: &str) {
+        self.recent
+            .retain(|path| !path.eq_ignore_ascii_case(file_path));
+        self.recent.insert(0, file_path.to_string());
+        self.recent.truncate(Self::MAX_RECENT);
+    }
+}
+
+/// Back/forward history of the selected files.
+#[derive(Resource, Default)]
+pub struct History {
+    paths: Vec<String>,
+    /// Index of the current file in `paths`.
+    index: usize,
+}
+
+impl History {
+    const MAX_LEN: usize = 100;
+
+    pub fn current(&self) -> Option<&str> {
+        self.paths.get(self.index).map(String::as_str)
+    }
+
+    /// Makes the file the current one, dropping the files after the current one.
+    /// Selecting the current file again, e.g. when navigating, does nothing.
+    pub fn push(&mut self, file_path: &str) {
+        if self.current() == Some(file_path) {
+            return;
+        }
+        if !self.paths.is_empty() {
+            self.paths.truncate(self.index + 1);
+        }
+        self.paths.push(file_path.to_string());
+        if self.paths.len() > Self::MAX_LEN {
+            self.paths.remove(0);
+        }
+        self.index = self.paths.len() - 1;
+    }
+
+    pub fn can_go_back(&self) -> bool {
+        self.index > 0
+    }
+
+    pub fn can_go_forward(&self) -> bool {
+        self.index + 1 < self.paths.len()
+    }
+
+    /// Moves to the previous file, returning the message selecting it.
+    pub fn back(&mut self) -> Option<FileSelected> {
+        if !self.can_go_back() {
+            return None;
+        }
+        self.index -= 1;
+        Some(FileSelected::new(self.paths[self.index].clone()))
+    }
+
+    /// Moves to the next file, returning the message selecting it.
+    pub fn forward(&mut self) -> Option<FileSelected> {
+        if !self.can_go_forward() {
+            return None;
+        }
+        self.index += 1;
+        Some(FileSelected::new(self.paths[self.index].clone()))
+    }
+}
+
+/// Adds the selected files to the history and the recent files.
+pub fn record_selection(
+    mut event_reader: MessageReader<FileSelected>,
+    mut history: ResMut<History>,
+    mut bookmarks: ResMut<Bookmarks>,
+) {
+    // Ignore all but the last event, like the file loading does
+    if let Some(event) = event_reader.read().last() {
+        history.push(&event.file_path);
+        bookmarks.add_recent(&event.file_path);
+    }
+}
+
+/// Navigates the history with Alt+Left/Alt+Right or the back/forward mouse buttons.
+pub fn navigate_history(
+    keys: Res<ButtonInput<KeyCode>>,
+    mouse_buttons: Res<ButtonInput<MouseButton>>,
+    mut egui_ctxs: EguiContexts,
+    mut history: ResMut<History>,
+    mut event_writer: MessageWriter<FileSelected>,
+) {
+    // Leave the arrow keys to the text fields
+    let keyboard_enabled = !egui_ctxs
+        .ctx_mut()
+        .is_ok_and(|ctx| ctx.wants_keyboard_input());
+    let alt = keys.any_pressed([KeyCode::AltLeft, KeyCode::AltRight]);
+
+    let message = if mouse_buttons.just_pressed(MouseButton::Back)
+        || (keyboard_enabled && alt && keys.just_pressed(KeyCode::ArrowLeft))
+    {
+        history.back()
+    } else if mouse_buttons.just_pressed(MouseButton::Forward)
+        || (keyboard_enabled && alt && keys.just_pressed(KeyCode::ArrowRight))
+    {
+        history.forward()
+    } else {
+        None
+    };
+    if let Some(message) = message {
+        event_writer.write(message);
+    }
+}
+
+/// Writes the bookmarks file whenever the bookmarks change.
+pub fn save_bookmarks(bookmarks: Res<Bookmarks>) {
+    if bookmarks.is_changed()
+        && !bookmarks.is_added()
+        && let Err(err) = bookmarks.save()
+    {
+        warn!("Failed to save bookmarks: {err}");
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_history() {
+        let mut history = History::default();
+        assert!(history.back().is_none());
+        history.push("a.m2");
+        history.push("b.m2");
+        history.push("c.m2");
+        assert_eq!(history.back().unwrap().file_path, "b.m2");
+
+        // Selecting the file navigated to keeps the forward history
+        history.push("b.m2");
+        assert!(history.can_go_forward());
+
+        assert_eq!(history.back().unwrap().file_path, "a.m2");
+        assert!(!history.can_go_back());
+        assert_eq!(history.forward().unwrap().file_path, "b.m2");
+
+        // Selecting another file drops the forward history
+        history.push("d.m2");
+        assert!(!history.can_go_forward());
+        assert_eq!(history.back().unwrap().file_path, "b.m2");
+        assert_eq!(history.back().unwrap().file_path, "a.m2");
+    }
+
+    #[test]
+    fn test_recent() {
+        let mut bookmarks = Bookmarks::default();
+        bookmarks.add_recent("a.m2");
+        bookmarks.add_recent("b.m2");
+        bookmarks.add_recent("A.m2");
+        assert_eq!(bookmarks.recent, ["A.m2", "b.m2"]);
+
+        bookmarks.toggle_favorite("a.m2");
+        assert!(bookmarks.is_favorite("A.M2"));
+        bookmarks.toggle_favorite("A.m2");
+        assert!(bookmarks.favorites.is_empty());
+    }
+}
@@ -0,0 +1,329 @@
+// Copyright © 2025
+// Author: Nocthir <nocthir@proton.me>
+// SPDX-License-Identifier: MIT or Apache-2.0
+
+//! State of the archive browser: virtual folder trees of the archive file
+//! lists, expanded folders, and file search across all the archives.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use bevy::prelude::*;
+
+use crate::data::{
+    archive::ArchiveInfoMap,
+    file::{FileCategory, FileInfoMap},
+};
+
+#[derive(Resource, Default)]
+pub struct FileBrowser {
+    pub search: String,
+    /// Query the search results have been computed for, `None` when outdated.
+    results_query: Option<String>,
+    /// Query waiting to be searched for, and when it was last edited.
+    pending_query: Option<(String, Instant)>,
+    results: Vec<String>,
+    /// Identifiers of the expanded folders, see [`FolderNode::flatten`].
+    expanded: HashSet<String>,
+    trees: HashMap<(PathBuf, FileCategory), FolderNode>,
+}
+
+impl FileBrowser {
+    /// Returns the visible rows of the folder tree of the files of a category
+    /// in an archive, building the tree on first use.
+    pub fn get_rows(
+        &mut self,
+        archive_path: &Path,
+        category: FileCategory,
+        paths: &[String],
+        id_prefix: &str,
+    ) -> Vec<BrowserRow<'_>> {
+        let tree = self
+            .trees
+            .entry((archive_path.to_path_buf(), category))
+            .or_insert_with(|| FolderNode::new(paths));
+        let mut rows = Vec::new();
+        tree.flatten(id_prefix, 0, &self.expanded, &mut rows);
+        rows
+    }
+
+    /// Expands the folder if collapsed, collapses it otherwise.
+    pub fn toggle(&mut self, folder_id: &str) {
+        if !self.expanded.remove(folder_id) {
+            self.expanded.insert(folder_id.to_string());
+        }
+    }
+
+    /// Delay after the last edit of the search query before searching, so
+    /// typing does not match the whole file list at every keystroke.
+    const SEARCH_DELAY: Duration = Duration::from_millis(150);
+
+    /// Returns the paths of the files matching the search query, best matches
+    /// first. The results of the previous query are returned until the query
+    /// has not been edited for [`Self::SEARCH_DELAY`].
+    pub fn get_results(&mut self, file_info_map: &FileInfoMap) -> &[String] {
+        if self.results_query.as_ref() == Some(&self.search) {
+            return &self.results;
+        }
+        let now = Instant::now();
+        match &self.pending_query {
+            Some((query, edited)) if *query == self.search => {
+                if now.duration_since(*edited) < Self::SEARCH_DELAY {
+                    return &self.results;
+                }
+            }
+            _ => {
+                self.pending_query = Some((self.search.clone(), now));
+                return &self.results;
+            }
+        }
+        self.pending_query = None;
+
+        let terms = get_query_terms(&self.search);
+        let mut results: Vec<(i32, &str)> = file_info_map
+            .get_files_by_lowercase_path()
+            .filter_map(|(lowercase_path, file)| {
+                Some((terms_score(&terms, lowercase_path)?, file.path.as_str()))
+            })
+            .collect();
+        results.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.cmp(b.1)));
+        self.results = results
+            .into_iter()
+            .map(|(_, path)| path.to_string())
+            .collect();
+        self.results_query = Some(self.search.clone());
+        &self.results
+    }
+
+    /// Whether the search results are outdated while the query is being typed.
+    pub fn is_searching(&self) -> bool {
+        self.pending_query.is_some()
+    }
+}
+
+/// Drops the folder trees and search results built from outdated file lists.
+pub fn refresh_file_browser(
+    mut file_browser: ResMut<FileBrowser>,
+    archive_info_map: Res<ArchiveInfoMap>,
+    file_info_map: Res<FileInfoMap>,
+) {
+    if archive_info_map.is_changed() {
+        file_browser.trees.clear();
+    }
+    if file_info_map.is_changed() {
+        file_browser.results_query = None;
+    }
+}
+
+/// Folder of the virtual directory tree built from backslash separated paths.
+#[derive(Default)]
+pub struct FolderNode {
+    pub name: String,
+    /// Lowercase name → subfolder.
+    pub folders: BTreeMap<String, FolderNode>,
+    /// Full paths of the files directly inside this folder.
+    pub files: Vec<String>,
+}
+
+/// Row of a flattened folder tree.
+pub enum BrowserRow<'a> {
+    Folder {
+        depth: usize,
+        name: &'a str,
+        id: String,
+        expanded: bool,
+    },
+    File {
+        depth: usize,
+        path: &'a str,
+    },
+}
+
+impl FolderNode {
+    pub fn new(paths: &[String]) -> Self {
+        let mut root = Self::default();
+        for path in paths {
+            let mut node = &mut root;
+            let mut names: Vec<&str> = path.split('\\').collect();
+            names.pop();
+            for name in names {
+                node = node
+                    .folders
+                    .entry(name.to_lowercase())
+                    .or_insert_with(|| FolderNode {
+                        name: name.to_string(),
+                        ..default()
+                    });
+            }
+            node.files.push(path.clone());
+        }
+        root.sort();
+        root
+    }
+
+    fn sort(&mut self) {
+        self.files.sort_by_key(|path| path.to_lowercase());
+        for folder in self.folders.values_mut() {
+            folder.sort();
+        }
+    }
+
+    /// Lists the rows of the visible folders and files, folders first.
+    /// A folder is identified by `id_prefix` followed by its lowercase path, and
+    /// its content is only listed when its identifier is in `expanded`.
+    pub fn flatten<'a>(
+        &'a self,
+        id_prefix: &str,
+        depth: usize,
+        expanded: &HashSet<String>,
+        rows: &mut Vec<BrowserRow<'a>>,
+    ) {
+        for (key, folder) in &self.folders {
+            let id = format!("{}\\{}", id_prefix, key);
+            let is_expanded = expanded.contains(&id);
+            rows.push(BrowserRow::Folder {
+                depth,
+                name: &folder.name,
+                id: id.clone(),
+                expanded: is_expanded,
+            });
+            if is_expanded {
+                folder.flatten(&id, depth + 1, expanded, rows);
+            }
+        }
+        rows.extend(
+            self.files
+                .iter()
+                .map(|path| BrowserRow::File { depth, path }),
+        );
+    }
+}
+
+/// Scores how well `candidate` matches `query`, higher is better, or returns
+/// `None` when it does not match. Every whitespace separated term of the query
+/// must match, ignoring case, either as a substring or, with a lower score, as
+/// a subsequence of characters.
+pub fn fuzzy_score(query: &str, candidate: &str) -> Option<i32> {
+    let candidate = candidate.to_lowercase().replace('/', "\\");
+    terms_score(&get_query_terms(query), &candidate)
+}
+
+/// Splits a query into lowercase, backslash separated terms.
+fn get_query_terms(query: &str) -> Vec<String> {
+    query
+        .split_whitespace()
+        .map(|term| term.to_lowercase().replace('/', "\\"))
+        .collect()
+}
+
+/// Same as [`fuzzy_score`] with the terms of the query, for a lowercase,
+/// backslash separated candidate.
+fn terms_score(terms: &[String], candidate: &str) -> Option<i32> {
+    let mut score = 0;
+    for term in terms {
+        score += term_score(term, candidate)?;
+    }
+    Some(score)
+}
+
+fn term_score(term: &str, candidate: &str) -> Option<i32> {
+    let term_len = term.chars().count() as i32;
+    // Prefer the last occurrence, which is more likely in the file name
+    if let Some(index) = candidate.rfind(term) {
+        let at_boundary = candidate[..index].ends_with(is_word_separator) || index == 0;
+        return Some(100 * term_len + if at_boundary { 50 } else { 0 });
+    }
+
+    let mut score = 0;
+    let mut candidate_chars = candidate.chars();
+    let mut previous = None;
+    let mut consecutive = false;
+    for term_char in term.chars() {
+        loop {
+            let candidate_char = candidate_chars.next()?;
+            if candidate_char == term_char {
+                score += if consecutive { 10 } else { 1 };
+                if previous.is_none_or(is_word_separator) {
+                    score += 5;
+                }
+                previous = Some(candidate_char);
+                consecutive = true;
+                break;
+            }
+            previous = Some(candidate_char);
+            consecutive = false;
+        }
+    }
+    Some(score)
+}
+
+fn is_word_separator(c: char) -> bool {
+    matches!(c, '\\' | '_' | '-' | '.' | ' ')
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_fuzzy_score() {
+        let path = "World\\Maps\\Azeroth\\Azeroth_32_48.adt";
+        assert!(fuzzy_score("", path).is_some());
+        assert!(fuzzy_score("azeroth_32", path).is_some());
+        assert!(fuzzy_score("maps/azeroth", path).is_some());
+        assert!(fuzzy_score("azeroth adt", path).is_some());
+        assert!(fuzzy_score("az3248", path).is_some());
+        assert!(fuzzy_score("kalimdor", path).is_none());
+        assert!(fuzzy_score("azeroth wmo", path).is_none());
+
+        // Substrings score higher than subsequences
+        let substring = fuzzy_score("wolf", "Creature\\Wolf\\Wolf.m2").unwrap();
+        let subsequence = fuzzy_score("wolf", "Creature\\Worgen\\Female.m2").unwrap();
+        assert!(substring > subsequence);
+    }
+
+    #[test]
+    fn test_folder_tree() {
+        let paths: Vec<String> = [
+            "World\\Maps\\Azeroth\\Azeroth_32_49.adt",
+            "world\\maps\\Azeroth\\Azeroth_32_48.adt",
+            "World\\Maps\\Kalimdor\\Kalimdor_32_48.adt",
+            "World\\readme.txt",
+        ]
+        .iter()
+        .map(|path| path.to_string())
+        .collect();
+        let tree = FolderNode::new(&paths);
+
+        let mut rows = Vec::new();
+        tree.flatten("", 0, &HashSet::new(), &mut rows);
+        assert_eq!(rows.len(), 1);
+
+        let expanded: HashSet<String> = ["\\world", "\\world\\maps", "\\world\\maps\\azeroth"]
+            .iter()
+            .map(|id| id.to_string())
+            .collect();
+        let mut rows = Vec::new();
+        tree.flatten("", 0, &expanded, &mut rows);
+        let rows: Vec<String> = rows
+            .iter()
+            .map(|row| match row {
+                BrowserRow::Folder { depth, name, .. } => format!("{depth} {name}"),
+                BrowserRow::File { depth, path } => format!("{depth} {path}"),
+            })
+            .collect();
+        assert_eq!(
+            rows,
+            [
+                "0 World",
+                "1 Maps",
+                "2 Azeroth",
+                "3 world\\maps\\Azeroth\\Azeroth_32_48.adt",
+                "3 World\\Maps\\Azeroth\\Azeroth_32_49.adt",
+                "2 Kalimdor",
+                "1 World\\readme.txt",
+            ]
+        );
+    }
+}
@@ -2,6 +2,8 @@
 // Author: Nocthir <nocthir@proton.me>
 // SPDX-License-Identifier: MIT or Apache-2.0
 
+use std::sync::Arc;
+
 use bevy::{asset::RecursiveDependencyLoadState, prelude::*};
 use bevy_egui::*;
 use bevy_inspector_egui::{
@@ -10,20 +12,38 @@ use bevy_inspector_egui::{
 };
 
 use crate::{
+    assets::archive::{ArchivePriority, ArchiveRegistry, FileArchiveMap},
+    camera::FocusRequested,
+    comparison::{Comparison, ComparisonIndexingTask},
     data::{
-        archive::{ArchiveInfo, ArchiveInfoMap},
-        file::{FileInfo, FileInfoMap},
+        CurrentFile, GameIndexingTask, SceneFile,
+        archive::{ArchiveErrorMap, ArchiveInfo, ArchiveInfoMap},
+        file::{FILE_CLASSES, FileClass, FileInfo, FileInfoMap},
+        load_error::LoadErrorMap,
+    },
+    settings::{Settings, TerrainSettings},
+    ui::{
+        ComparisonFileSelected, ComparisonGamePathSelected, FileRemoved, FileSelected,
+        GamePathSelected,
+        bookmarks::{Bookmarks, History},
+        file_browser::{BrowserRow, FileBrowser},
+        get_file_icon, load_error_ui,
     },
-    settings::TerrainSettings,
-    ui::{FileSelected, get_file_icon},
 };
 
+/// Maximum height of the file list of a category in an archive.
+const FILE_LIST_HEIGHT: f32 = 400.0;
+
 pub fn ui(world: &mut World, context: &mut EguiContext) -> egui::InnerResponse<()> {
     egui::SidePanel::left("info_panel")
         .resizable(true)
         .min_width(240.0)
         .default_width(320.0)
         .show(context.get_mut(), |ui| {
+            game_path_ui(world, ui);
+            bookmarks_ui(world, ui);
+            scene_ui(world, ui);
+
             egui::CollapsingHeader::new("Terrain Settings")
                 .default_open(false)
                 .show(ui, |ui| {
@@ -32,6 +52,13 @@ pub fn ui(world: &mut World, context: &mut EguiContext) -> egui::InnerResponse<(
 
             ui.separator();
 
+            archive_errors_ui(world, ui);
+
+            if search_ui(world, ui) {
+                search_results_ui(world, ui);
+                return;
+            }
+
             // Single scroll area with both vertical and horizontal scrolling so
             // the horizontal scrollbar is rendered at the bottom of the panel.
             egui::ScrollArea::both()
@@ -43,6 +70,411 @@ pub fn ui(world: &mut World, context: &mut EguiContext) -> egui::InnerResponse<(
         })
 }
 
+/// Edits the game path and switches to the game installation when loaded.
+fn game_path_ui(world: &mut World, ui: &mut egui::Ui) {
+    let Some(settings) = world.get_resource::<Settings>() else {
+        return;
+    };
+    let current_game_path = settings.game_path.clone();
+
+    let id = egui::Id::new("game_path");
+    let mut game_path = ui.data_mut(|data| {
+        data.get_temp_mut_or_insert_with(id, || current_game_path.clone())
+            .clone()
+    });
+
+    let mut selected = None;
+    egui::CollapsingHeader::new("Game")
+        .default_open(false)
+        .show(ui, |ui| {
+            ui.horizontal(|ui| {
+                let response = ui.text_edit_singleline(&mut game_path);
+                let submitted =
+                    response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+                let changed = game_path != current_game_path;
+                let clicked = ui.add_enabled(changed, egui::Button::new("Load")).clicked();
+                if changed && (clicked || submitted) {
+                    selected.replace(GamePathSelected::new(game_path.clone()));
+                }
+            });
+            ui.weak(format!("Current: {}", current_game_path));
+            if world.contains_resource::<GameIndexingTask>() {
+                ui.horizontal(|ui| {
+                    ui.spinner();
+                    ui.label("Indexing archives…");
+                });
+            }
+        });
+    ui.data_mut(|data| data.insert_temp(id, game_path));
+
+    if let Some(message) = selected {
+        world.write_message(message);
+    }
+
+    ui.separator();
+}
+
+/// Shows the back/forward buttons, the favorite toggle of the current file,
+/// and the favorite and recent files.
+fn bookmarks_ui(world: &mut World, ui: &mut egui::Ui) {
+    let mut message = None;
+    let mut toggled_favorite = None;
+    world.resource_scope(|world, mut history: Mut<History>| {
+        let Some(bookmarks) = world.get_resource::<Bookmarks>() else {
+            return;
+        };
+        let file_info_map = world.get_resource::<FileInfoMap>();
+
+        ui.horizontal(|ui| {
+            let back = ui
+                .add_enabled(history.can_go_back(), egui::Button::new("⬅"))
+                .on_hover_text("Back (Alt+←)");
+            if back.clicked() {
+                message = history.back();
+            }
+            let forward = ui
+                .add_enabled(history.can_go_forward(), egui::Button::new("➡"))
+                .on_hover_text("Forward (Alt+→)");
+            if forward.clicked() {
+                message = history.forward();
+            }
+            if let Some(current_path) = history.current() {
+                let is_favorite = bookmarks.is_favorite(current_path);
+                let (icon, hover_text) = if is_favorite {
+                    ("★", "Remove from favorites")
+                } else {
+                    ("☆", "Add to favorites")
+                };
+                if ui.button(icon).on_hover_text(hover_text).clicked() {
+                    toggled_favorite.replace(current_path.to_string());
+                }
+                ui.label(current_path.rsplit('\\').next().unwrap_or(current_path))
+                    .on_hover_text(current_path);
+            }
+        });
+
+        let lists = [
+            ("★ Favorites", &bookmarks.favorites),
+            ("🕘 Recent", &bookmarks.recent),
+        ];
+        for (label, paths) in lists {
+            egui::CollapsingHeader::new(format!("{} ({})", label, paths.len()))
+                .default_open(false)
+                .enabled(!paths.is_empty())
+                .show(ui, |ui| {
+                    for path in paths {
+                        // Files of another game installation can not be opened
+                        let available = file_info_map.is_some_and(|map| map.get_file(path).is_ok());
+                        let file_name = path.rsplit('\\').next().unwrap_or(path);
+                        let response = ui
+                            .add_enabled_ui(available, |ui| ui.selectable_label(false, file_name))
+                            .inner
+                            .on_hover_text(path)
+                            .on_disabled_hover_text(format!("{} is not available", path));
+                        if response.clicked() {
+                            message.replace(FileSelected::new(path.clone()));
+                        }
+                    }
+                });
+        }
+    });
+
+    if let Some(file_path) = toggled_favorite {
+        world
+            .resource_mut::<Bookmarks>()
+            .toggle_favorite(&file_path);
+    }
+    if let Some(message) = message {
+        world.write_message(message);
+    }
+
+    ui.separator();
+}
+
+/// Scene outliner: lists the files of the scene to select, pin, hide, focus,
+/// move or remove them, and to choose the files compared side by side.
+fn scene_ui(world: &mut World, ui: &mut egui::Ui) {
+    let mut query = world.query::<(
+        Entity,
+        &mut SceneFile,
+        Has<CurrentFile>,
+        Option<&mut Visibility>,
+        Option<&mut Transform>,
+    )>();
+    let mut entities: Vec<Entity> = query.iter(world).map(|(entity, ..)| entity).collect();
+    if entities.is_empty() {
+        return;
+    }
+    // Spawn order
+    entities.sort();
+
+    let comparison = world.resource::<Comparison>();
+    let (mut compare, mut left, mut right) =
+        (comparison.enabled, comparison.left, comparison.right);
+
+    let mut selected = None;
+    let mut focused = None;
+    let mut removed = None;
+    egui::CollapsingHeader::new(format!("🎬 Scene ({})", entities.len()))
+        .default_open(true)
+        .show(ui, |ui| {
+            ui.toggle_value(&mut compare, "⚖ Compare")
+                .on_hover_text("Show two files side by side");
+            for entity in entities {
+                let Ok((entity, mut scene_file, is_current, visibility, transform)) =
+                    query.get_mut(world, entity)
+                else {
+                    continue;
+                };
+
+                ui.horizontal(|ui| {
+                    if compare {
+                        if ui
+                            .selectable_label(left == Some(entity), "A")
+                            .on_hover_text("Show on the left")
+                            .clicked()
+                        {
+                            if right == Some(entity) {
+                                right = left;
+                            }
+                            left = Some(entity);
+                        }
+                        if ui
+                            .selectable_label(right == Some(entity), "B")
+                            .on_hover_text("Show on the right")
+                            .clicked()
+                        {
+                            if left == Some(entity) {
+                                left = right;
+                            }
+                            right = Some(entity);
+                        }
+                    }
+                    let pinned = scene_file.pinned;
+                    if ui
+                        .selectable_label(pinned, "📌")
+                        .on_hover_text("Keep in the scene when selecting another file")
+                        .clicked()
+                    {
+                        scene_file.pinned = !pinned;
+                    }
+                    if let Some(mut visibility) = visibility {
+                        let visible = *visibility != Visibility::Hidden;
+                        let hover_text = if visible { "Hide" } else { "Show" };
+                        if ui
+                            .selectable_label(visible, "👁")
+                            .on_hover_text(hover_text)
+                            .clicked()
+                        {
+                            *visibility = if visible {
+                                Visibility::Hidden
+                            } else {
+                                Visibility::Inherited
+                            };
+                        }
+                        if ui.button("🎯").on_hover_text("Focus").clicked() {
+                            focused.replace(FocusRequested { entity });
+                        }
+                    }
+                    if ui.button("✖").on_hover_text("Remove").clicked() {
+                        removed.replace(FileRemoved::new(
+                            scene_file.path.clone(),
+                            scene_file.comparison,
+                        ));
+                    }
+                    let file_name = scene_file.path.rsplit('\\').next().unwrap_or_default();
+                    if scene_file.comparison {
+                        // Not in the file map of the current installation
+                        ui.label(format!("🔀 {file_name}")).on_hover_text(format!(
+                            "{} from the comparison game installation",
+                            scene_file.path
+                        ));
+                    } else if ui
+                        .selectable_label(is_current, file_name)
+                        .on_hover_text(&scene_file.path)
+                        .clicked()
+                    {
+                        selected.replace(FileSelected::new(scene_file.path.clone()));
+                    }
+                });
+
+                if let Some(transform) = transform {
+                    egui::CollapsingHeader::new("Transform")
+                        .id_salt(entity)
+                        .default_open(false)
+                        .show(ui, |ui| transform_ui(transform, ui));
+                }
+            }
+        });
+
+    // Only write the comparison when changed, as render layers are updated then
+    let comparison = world.resource::<Comparison>();
+    if (comparison.enabled, comparison.left, comparison.right) != (compare, left, right) {
+        let mut comparison = world.resource_mut::<Comparison>();
+        comparison.enabled = compare;
+        comparison.left = left;
+        comparison.right = right;
+    }
+
+    if let Some(message) = selected {
+        world.write_message(message);
+    }
+    if let Some(message) = focused {
+        world.write_message(message);
+    }
+    if let Some(message) = removed {
+        world.write_message(message);
+    }
+
+    if compare {
+        comparison_game_ui(world, ui);
+    }
+
+    ui.separator();
+}
+
+/// Edits the comparison game path, and loads the current file from that game
+/// installation to compare both versions.
+fn comparison_game_ui(world: &mut World, ui: &mut egui::Ui) {
+    let Some(settings) = world.get_resource::<Settings>() else {
+        return;
+    };
+    let current_game_path = settings.comparison_game_path.clone().unwrap_or_default();
+    let is_indexing = world.contains_resource::<ComparisonIndexingTask>();
+    let current_file = world
+        .query_filtered::<&SceneFile, With<CurrentFile>>()
+        .iter(world)
+        .next()
+        .map(|scene_file| scene_file.path.clone());
+
+    let id = egui::Id::new("comparison_game_path");
+    let mut game_path = ui.data_mut(|data| {
+        data.get_temp_mut_or_insert_with(id, || current_game_path.clone())
+            .clone()
+    });
+
+    let mut game_selected = None;
+    let mut file_selected = None;
+    ui.label("Comparison game installation");
+    ui.horizontal(|ui| {
+        let response = ui.text_edit_singleline(&mut game_path);
+        let submitted = response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+        let changed = game_path != current_game_path;
+        let clicked = ui.add_enabled(changed, egui::Button::new("Load")).clicked();
+        if changed && (clicked || submitted) {
+            game_selected.replace(ComparisonGamePathSelected::new(game_path.clone()));
+        }
+    });
+    if is_indexing {
+        ui.horizontal(|ui| {
+            ui.spinner();
+            ui.label("Indexing archives…");
+        });
+    }
+    let enabled = !current_game_path.is_empty() && !is_indexing && current_file.is_some();
+    if ui
+        .add_enabled(enabled, egui::Button::new("🔀 Open the current file in B"))
+        .on_hover_text("Load the current file from the comparison game installation")
+        .clicked()
+        && let Some(file_path) = current_file
+    {
+        file_selected.replace(ComparisonFileSelected::new(file_path));
+    }
+    ui.data_mut(|data| data.insert_temp(id, game_path));
+
+    if let Some(message) = game_selected {
+        world.write_message(message);
+    }
+    if let Some(message) = file_selected {
+        world.write_message(message);
+    }
+}
+
+/// Edits the translation, rotation (degrees) and uniform scale of a transform.
+/// The transform is only written when edited, so unchanged scenes are not
+/// propagated again.
+fn transform_ui(mut transform: Mut<Transform>, ui: &mut egui::Ui) {
+    let mut translation = transform.translation;
+    let (yaw, pitch, roll) = transform.rotation.to_euler(EulerRot::YXZ);
+    let mut rotation = Vec3::new(pitch, yaw, roll) * (180.0 / std::f32::consts::PI);
+    let mut scale = transform.scale.x;
+
+    let mut changed = false;
+    egui::Grid::new("transform").num_columns(4).show(ui, |ui| {
+        ui.label("Position");
+        for value in translation.as_mut() {
+            changed |= ui.add(egui::DragValue::new(value).speed(0.1)).changed();
+        }
+        ui.end_row();
+        ui.label("Rotation");
+        for value in rotation.as_mut() {
+            changed |= ui
+                .add(egui::DragValue::new(value).speed(1.0).suffix("°"))
+                .changed();
+        }
+        ui.end_row();
+        ui.label("Scale");
+        changed |= ui
+            .add(
+                egui::DragValue::new(&mut scale)
+                    .speed(0.01)
+                    .range(0.01..=100.0),
+            )
+            .changed();
+        ui.end_row();
+    });
+
+    if changed {
+        let rotation = rotation * (std::f32::consts::PI / 180.0);
+        transform.translation = translation;
+        transform.rotation = Quat::from_euler(EulerRot::YXZ, rotation.y, rotation.x, rotation.z);
+        transform.scale = Vec3::splat(scale);
+    }
+}
+
+/// Edits the file search query. Returns whether a search is active.
+fn search_ui(world: &mut World, ui: &mut egui::Ui) -> bool {
+    let Some(mut file_browser) = world.get_resource_mut::<FileBrowser>() else {
+        return false;
+    };
+    ui.horizontal(|ui| {
+        ui.add(
+            egui::TextEdit::singleline(&mut file_browser.search)
+                .hint_text("🔍 Search files")
+                .desired_width(f32::INFINITY),
+        );
+    });
+    ui.separator();
+    !file_browser.search.trim().is_empty()
+}
+
+/// Lists the archives that could not be read, with their error message.
+fn archive_errors_ui(world: &World, ui: &mut egui::Ui) {
+    let Some(archive_error_map) = world.get_resource::<ArchiveErrorMap>() else {
+        return;
+    };
+    if archive_error_map.map.is_empty() {
+        return;
+    }
+
+    let label = format!("⚠ Broken archives ({})", archive_error_map.map.len());
+    egui::CollapsingHeader::new(label)
+        .default_open(true)
+        .show(ui, |ui| {
+            for (archive_path, message) in &archive_error_map.map {
+                let archive_file_name = archive_path
+                    .file_name()
+                    .unwrap_or_default()
+                    .to_string_lossy();
+                ui.label(format!("⛃ {}", archive_file_name))
+                    .on_hover_text(archive_path.display().to_string());
+                ui.colored_label(egui::Color32::RED, message);
+            }
+        });
+
+    ui.separator();
+}
+
 impl InspectorPrimitive for ArchiveInfoMap {
     fn ui(
         &mut self,
@@ -66,7 +498,9 @@ impl InspectorPrimitive for ArchiveInfoMap {
             ui.label("No world available");
             return;
         };
-        for archive in self.map.values() {
+        let mut archives: Vec<&ArchiveInfo> = self.map.values().collect();
+        archives.sort_by_cached_key(|archive| ArchivePriority::new(&archive.path));
+        for archive in archives {
             archive_ui(archive, unsafe { world.world().world_mut() }, ui);
         }
     }
@@ -79,68 +513,30 @@ fn archive_ui(archive: &ArchiveInfo, world: &mut World, ui: &mut egui::Ui) {
         .unwrap_or_default()
         .to_str()
         .unwrap_or("Unknown");
-    let label = format!("⛃ {}", archive_file_name);
+    let mut label = format!("⛃ {}", archive_file_name);
+    if archive.unnamed_count > 0 {
+        label.push_str(&format!(" ({} unnamed)", archive.unnamed_count));
+    }
 
     egui::CollapsingHeader::new(label)
         .default_open(false)
         .show(ui, |ui| {
-            let Some(file_info_map) = world.get_resource::<FileInfoMap>() else {
-                ui.colored_label(egui::Color32::RED, "No file info map available");
-                return;
-            };
+            let message = world.resource_scope(|world, mut file_browser: Mut<FileBrowser>| {
+                let Some(context) = FileRowContext::new(world) else {
+                    ui.colored_label(egui::Color32::RED, "No file info available");
+                    return None;
+                };
 
-            let Some(asset_server) = world.get_resource::<AssetServer>() else {
-                ui.colored_label(egui::Color32::RED, "No asset server available");
-                return;
-            };
-
-            let mut message = None;
-
-            if let Some(msg) = archive_files_ui(
-                "Textures",
-                &archive.texture_paths,
-                file_info_map,
-                ui,
-                asset_server,
-            ) {
-                message.replace(msg);
-            }
-            if let Some(msg) = archive_files_ui(
-                "Models",
-                &archive.model_paths,
-                file_info_map,
-                ui,
-                asset_server,
-            ) {
-                message.replace(msg);
-            }
-            if let Some(msg) = archive_files_ui(
-                "World Models",
-                &archive.world_model_paths,
-                file_info_map,
-                ui,
-                asset_server,
-            ) {
-                message.replace(msg);
-            }
-            if let Some(msg) = archive_files_ui(
-                "World Maps",
-                &archive.world_map_paths,
-                file_info_map,
-                ui,
-                asset_server,
-            ) {
-                message.replace(msg);
-            }
-            if let Some(msg) = archive_files_ui(
-                "Data Bases",
-                &archive.data_base_paths,
-                file_info_map,
-                ui,
-                asset_server,
-            ) {
-                message.replace(msg);
-            }
+                let mut message = None;
+                for class in FILE_CLASSES {
+                    if let Some(msg) =
+                        archive_files_ui(archive, class, &mut file_browser, &context, ui)
+                    {
+                        message.replace(msg);
+                    }
+                }
+                message
+            });
 
             if let Some(message) = message {
                 world.write_message(message);
@@ -148,87 +544,200 @@ fn archive_ui(archive: &ArchiveInfo, world: &mut World, ui: &mut egui::Ui) {
         });
 }
 
-fn archive_files_ui<S: AsRef<str>>(
-    label: S,
-    paths: &[String],
-    file_info_map: &FileInfoMap,
+/// Shows the files of a category in an archive as a folder tree.
+/// Only the visible rows are laid out, so large archives stay responsive.
+fn archive_files_ui(
+    archive: &ArchiveInfo,
+    class: &FileClass,
+    file_browser: &mut FileBrowser,
+    context: &FileRowContext,
     ui: &mut egui::Ui,
-    asset_server: &AssetServer,
 ) -> Option<FileSelected> {
-    let response = egui::CollapsingHeader::new(label.as_ref())
+    let paths = archive.get_file_paths(class.category);
+    let response = egui::CollapsingHeader::new(class.label)
         .enabled(!paths.is_empty())
         .show(ui, |ui| {
+            let id_prefix = format!("{}|{}", archive.path.display(), class.label);
+            let rows = file_browser.get_rows(&archive.path, class.category, paths, &id_prefix);
+            let indent = ui.spacing().indent;
+            let row_height = ui.spacing().interact_size.y;
+
             let mut ret = None;
-            for path in paths {
-                if let Ok(file_info) = file_info_map.get_file(path) {
-                    if let Some(message) = archive_file_ui(file_info, ui, asset_server) {
-                        ret.replace(message);
+            let mut toggled = None;
+            egui::ScrollArea::vertical()
+                .id_salt(&id_prefix)
+                .max_height(FILE_LIST_HEIGHT)
+                .auto_shrink([false, true])
+                .show_rows(ui, row_height, rows.len(), |ui, range| {
+                    for row in &rows[range] {
+                        match row {
+                            BrowserRow::Folder {
+                                depth,
+                                name,
+                                id,
+                                expanded,
+                            } => {
+                                ui.horizontal(|ui| {
+                                    ui.add_space(indent * *depth as f32);
+                                    let icon = if *expanded { "▼" } else { "▶" };
+                                    let label = format!("{} 📁 {}", icon, name);
+                                    if ui.selectable_label(false, label).clicked() {
+                                        toggled.replace(id.clone());
+                                    }
+                                });
+                            }
+                            BrowserRow::File { depth, path } => {
+                                let Ok(file_info) = context.file_info_map.get_file(path) else {
+                                    ui.colored_label(
+                                        egui::Color32::RED,
+                                        format!("Failed to get info for {}", path),
+                                    );
+                                    continue;
+                                };
+                                let file_name = path.rsplit('\\').next().unwrap_or(path);
+                                ui.horizontal(|ui| {
+                                    ui.add_space(indent * *depth as f32);
+                                    if file_ui(file_info, file_name, context, ui) {
+                                        ret.replace(FileSelected {
+                                            file_path: file_info.path.to_owned(),
+                                        });
+                                    }
+                                });
+                            }
+                        }
                     }
-                } else {
-                    ui.colored_label(
-                        egui::Color32::RED,
-                        format!("Failed to get info for {}", path),
-                    );
-                }
+                });
+
+            if let Some(folder_id) = toggled {
+                file_browser.toggle(&folder_id);
             }
             ret
         });
     response.body_returned.flatten()
 }
 
-fn archive_file_ui(
-    file_info: &FileInfo,
-    ui: &mut egui::Ui,
-    asset_server: &AssetServer,
-) -> Option<FileSelected> {
-    let r = file_ui(file_info, ui, asset_server);
-    if r.header_response.clicked() && !r.header_response.is_tooltip_open() {
-        Some(FileSelected {
-            file_path: file_info.path.to_owned(),
-        })
-    } else {
-        None
+/// Lists the files matching the search query across all the archives.
+fn search_results_ui(world: &mut World, ui: &mut egui::Ui) {
+    let message = world.resource_scope(|world, mut file_browser: Mut<FileBrowser>| {
+        let context = FileRowContext::new(world)?;
+        let searching = file_browser.is_searching();
+        let results = file_browser.get_results(context.file_info_map);
+        ui.horizontal(|ui| {
+            ui.weak(format!("{} files found", results.len()));
+            if searching {
+                ui.spinner();
+            }
+        });
+
+        let mut ret = None;
+        let row_height = ui.spacing().interact_size.y;
+        egui::ScrollArea::both()
+            .auto_shrink([false, false])
+            .id_salt("search_results")
+            .show_rows(ui, row_height, results.len(), |ui, range| {
+                for path in &results[range] {
+                    let Ok(file_info) = context.file_info_map.get_file(path) else {
+                        continue;
+                    };
+                    let clicked = ui
+                        .horizontal(|ui| file_ui(file_info, path, &context, ui))
+                        .inner;
+                    if clicked {
+                        ret.replace(FileSelected {
+                            file_path: path.clone(),
+                        });
+                    }
+                }
+            });
+        ret
+    });
+
+    if let Some(message) = message {
+        world.write_message(message);
     }
 }
 
-fn file_ui(
-    file_info: &FileInfo,
-    ui: &mut egui::Ui,
-    asset_server: &AssetServer,
-) -> egui::collapsing_header::CollapsingResponse<()> {
-    let load_state = file_info.get_load_state(asset_server);
-    let mut error_message = None;
-    if let RecursiveDependencyLoadState::Failed(err) = &load_state {
-        error_message.replace(err.to_string());
+/// Resources needed to show file rows.
+struct FileRowContext<'w> {
+    file_info_map: &'w FileInfoMap,
+    file_archive_map: Arc<FileArchiveMap>,
+    asset_server: &'w AssetServer,
+    load_error_map: &'w LoadErrorMap,
+}
+
+impl<'w> FileRowContext<'w> {
+    fn new(world: &'w World) -> Option<Self> {
+        Some(Self {
+            file_info_map: world.get_resource::<FileInfoMap>()?,
+            file_archive_map: world.get_resource::<ArchiveRegistry>()?.get(),
+            asset_server: world.get_resource::<AssetServer>()?,
+            load_error_map: world.get_resource::<LoadErrorMap>()?,
+        })
     }
+}
+
+/// Shows a single line file row with its load state, and its details on hover.
+/// Returns whether the file was clicked.
+fn file_ui(file_info: &FileInfo, label: &str, context: &FileRowContext, ui: &mut egui::Ui) -> bool {
+    let file_archive_map = &context.file_archive_map;
+    let load_state = file_info.get_load_state(context.asset_server);
+    // Failures are remembered after the file is unloaded
+    let load_error = context
+        .load_error_map
+        .get_file_error(file_info, context.asset_server);
+    let (state_icon, state_color) = match &load_state {
+        RecursiveDependencyLoadState::NotLoaded if load_error.is_some() => {
+            ("✖", egui::Color32::DARK_RED)
+        }
+        RecursiveDependencyLoadState::NotLoaded => ("▶", ui.style().visuals.text_color()),
+        RecursiveDependencyLoadState::Loading => ("⏳", ui.style().visuals.text_color()),
+        RecursiveDependencyLoadState::Loaded => ("✔", egui::Color32::CYAN),
+        RecursiveDependencyLoadState::Failed(_) => ("✖", egui::Color32::RED),
+    };
+    ui.colored_label(state_color, state_icon);
 
     let file_icon = get_file_icon(&file_info.data_type);
-    egui::CollapsingHeader::new(format!("{} {}", file_icon, file_info.path))
-        .icon(move |ui, _, response| {
-            let pos = response.rect.center();
-            let anchor = egui::Align2::CENTER_CENTER;
-            let font_id = egui::TextStyle::Button.resolve(ui.style());
-            let text_color = ui.style().visuals.text_color();
-            match load_state {
-                RecursiveDependencyLoadState::NotLoaded => {
-                    ui.painter().text(pos, anchor, "▶", font_id, text_color);
-                }
-                RecursiveDependencyLoadState::Loading => {
-                    ui.painter().text(pos, anchor, "⏳", font_id, text_color);
-                }
-                RecursiveDependencyLoadState::Loaded => {
-                    ui.painter()
-                        .text(pos, anchor, "✔", font_id, egui::Color32::CYAN);
-                }
-                RecursiveDependencyLoadState::Failed(_) => {
-                    ui.painter()
-                        .text(pos, anchor, "✖", font_id, egui::Color32::RED);
-                }
-            };
-        })
-        .show(ui, |ui| {
-            if let Some(msg) = error_message {
-                ui.colored_label(egui::Color32::RED, msg);
+    let overlay_marker = if file_archive_map.get_overlay_path(&file_info.path).is_some() {
+        " 📝"
+    } else {
+        ""
+    };
+    let label = format!("{} {}{}", file_icon, label, overlay_marker);
+    ui.selectable_label(false, label)
+        .on_hover_ui(|ui| {
+            ui.label(&file_info.path);
+            if let Some(load_error) = &load_error {
+                load_error_ui(&file_info.path, load_error, ui);
             }
+            archive_chain_ui(&file_info.path, file_archive_map, ui);
         })
+        .clicked()
+}
+
+/// Lists the archives containing the file, highlighting the one it is read from.
+fn archive_chain_ui(file_path: &str, file_archive_map: &FileArchiveMap, ui: &mut egui::Ui) {
+    let overlay_path = file_archive_map.get_overlay_path(file_path);
+    let archive_chain = file_archive_map
+        .get_archive_chain(file_path)
+        .unwrap_or_default();
+    if let Some(overlay_path) = overlay_path {
+        ui.colored_label(
+            egui::Color32::YELLOW,
+            format!("📝 Overridden by {}", overlay_path.display()),
+        );
+    } else if archive_chain.len() < 2 {
+        return;
+    }
+    ui.label("Shadowed archives:");
+    for (index, archive_path) in archive_chain.iter().enumerate().rev() {
+        let archive_name = archive_path
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy();
+        if overlay_path.is_none() && index == archive_chain.len() - 1 {
+            ui.colored_label(egui::Color32::CYAN, format!("⛃ {}", archive_name));
+        } else {
+            ui.weak(format!("⛃ {}", archive_name));
+        }
+    }
 }
@@ -14,29 +14,47 @@ use bevy_egui::*;
 use bevy_inspector_egui::inspector_egui_impls::InspectorEguiImpl;
 
 use crate::{
-    assets::{
-        material::TerrainMaterial,
-        model::{self, Model},
-        world_map,
-        world_model::{self, WorldModel},
-    },
-    data::{archive::ArchiveInfoMap, file},
+    assets::{material::TerrainMaterial, model::Model, world_model::WorldModel},
+    camera::PanOrbitSettings,
+    comparison::ComparisonCamera,
+    data::{archive::ArchiveInfoMap, file, load_error::LoadError},
     settings::{self, FileSettings},
 };
 
+mod bookmarks;
+mod file_browser;
 mod left_panel;
 mod right_panel;
+mod texture_inspector;
 
 pub struct UiPlugin;
 
 impl Plugin for UiPlugin {
     fn build(&self, app: &mut App) {
         app.add_message::<FileSelected>()
+            .add_message::<GamePathSelected>()
+            .add_message::<ComparisonGamePathSelected>()
+            .add_message::<ComparisonFileSelected>()
+            .add_message::<FileRemoved>()
+            .init_resource::<file_browser::FileBrowser>()
+            .insert_resource(bookmarks::Bookmarks::load())
+            .init_resource::<bookmarks::History>()
+            .init_resource::<texture_inspector::TextureInspector>()
             .register_type_data::<ArchiveInfoMap, InspectorEguiImpl>()
             .register_type_data::<Model, InspectorEguiImpl>()
             .register_type_data::<WorldModel, InspectorEguiImpl>()
             .register_type_data::<TerrainMaterial, InspectorEguiImpl>()
             .add_systems(Startup, setup_ui)
+            .add_systems(
+                Update,
+                (
+                    file_browser::refresh_file_browser,
+                    bookmarks::navigate_history,
+                    bookmarks::record_selection,
+                    bookmarks::save_bookmarks,
+                )
+                    .chain(),
+            )
             .add_systems(EguiPrimaryContextPass, inspector_ui);
     }
 }
@@ -78,9 +96,8 @@ impl FileSelected {
     }
 
     pub fn has_scene_root(&self) -> bool {
-        model::is_model_extension(&self.file_path)
-            || world_model::is_world_model_extension(&self.file_path)
-            || world_map::is_world_map_extension(&self.file_path)
+        file::FileCategory::from_path(&self.file_path)
+            .is_some_and(|category| category.class().has_scene_root)
     }
 
     pub fn get_asset_path(&self) -> String {
@@ -92,6 +109,65 @@ impl FileSelected {
     }
 }
 
+/// Requests removing a file from the scene.
+#[derive(Message)]
+pub struct FileRemoved {
+    pub file_path: String,
+    /// Whether the file is read from the comparison game installation.
+    pub comparison: bool,
+}
+
+impl FileRemoved {
+    pub fn new(file_path: String, comparison: bool) -> Self {
+        info!("File removed: {}", file_path);
+        Self {
+            file_path,
+            comparison,
+        }
+    }
+}
+
+/// Requests switching to the game installation found at `game_path`.
+#[derive(Message)]
+pub struct GamePathSelected {
+    pub game_path: String,
+}
+
+impl GamePathSelected {
+    pub fn new(game_path: String) -> Self {
+        info!("Game path selected: {}", game_path);
+        Self { game_path }
+    }
+}
+
+/// Requests indexing the game installation found at `game_path` to compare
+/// its files with the ones of the current installation.
+#[derive(Message)]
+pub struct ComparisonGamePathSelected {
+    pub game_path: String,
+}
+
+impl ComparisonGamePathSelected {
+    pub fn new(game_path: String) -> Self {
+        info!("Comparison game path selected: {}", game_path);
+        Self { game_path }
+    }
+}
+
+/// Requests loading a file from the comparison game installation, and
+/// showing it on the right of the comparison.
+#[derive(Message)]
+pub struct ComparisonFileSelected {
+    pub file_path: String,
+}
+
+impl ComparisonFileSelected {
+    pub fn new(file_path: String) -> Self {
+        info!("Comparison file selected: {}", file_path);
+        Self { file_path }
+    }
+}
+
 impl From<&FileSettings> for FileSelected {
     fn from(settings: &FileSettings) -> Self {
         Self {
@@ -100,8 +176,11 @@ impl From<&FileSettings> for FileSelected {
     }
 }
 
-pub fn select_default_model(mut event_writer: MessageWriter<FileSelected>) {
-    if let Some(default_model_path) = settings::Settings::get().test_model_path.clone() {
+pub fn select_default_model(
+    settings: Res<settings::Settings>,
+    mut event_writer: MessageWriter<FileSelected>,
+) {
+    if let Some(default_model_path) = settings.test_model_path.clone() {
         event_writer.write(FileSelected::new(default_model_path));
     }
 }
@@ -153,10 +232,31 @@ fn adjust_viewport(
     let pos = UVec2::new(left_phys, 0);
     let size = UVec2::new(viewport_width, window.physical_height());
 
+    // Split the viewport in two halves when comparing files
+    let comparison_camera = world
+        .query_filtered::<Entity, With<ComparisonCamera>>()
+        .iter(world)
+        .next();
+    let main_camera = world
+        .query_filtered::<Entity, With<PanOrbitSettings>>()
+        .single(world)?;
+    if let Some(comparison_camera) = comparison_camera {
+        let half_size = UVec2::new(size.x / 2, size.y);
+        set_viewport(world, main_camera, pos, half_size);
+        let right_pos = UVec2::new(pos.x + half_size.x, pos.y);
+        let right_size = UVec2::new(size.x - half_size.x, size.y);
+        set_viewport(world, comparison_camera, right_pos, right_size);
+    } else {
+        set_viewport(world, main_camera, pos, size);
+    }
+    Ok(())
+}
+
+fn set_viewport(world: &mut World, entity: Entity, pos: UVec2, size: UVec2) {
+    let Some(mut camera) = world.get_mut::<Camera>(entity) else {
+        return;
+    };
     // Only update if changed to avoid unnecessary render graph invalidation.
-    let mut camera = world
-        .query_filtered::<&mut Camera, Without<EguiContext>>()
-        .single_mut(world)?;
     let needs_update = match &camera.viewport {
         Some(vp) => vp.physical_position != pos || vp.physical_size != size,
         None => true,
@@ -168,7 +268,6 @@ fn adjust_viewport(
             ..default()
         });
     }
-    Ok(())
 }
 
 fn get_image_map(world: &mut World) -> HashMap<Handle<Image>, egui::TextureId> {
@@ -206,12 +305,19 @@ fn get_image_map(world: &mut World) -> HashMap<Handle<Image>, egui::TextureId> {
 }
 
 fn get_file_icon(data_type: &file::DataType) -> &'static str {
-    match data_type {
-        file::DataType::Texture(_) => "🖼",
-        file::DataType::Model(_) => "📦",
-        file::DataType::WorldModel(_) => "🏰",
-        file::DataType::WorldMap(_) => "🗺",
-        file::DataType::DataBase(_) => "📚",
-        file::DataType::Unknown => "❓",
+    data_type
+        .category()
+        .map(|category| category.class().icon)
+        .unwrap_or("❓")
+}
+
+/// Shows why a file failed to load, naming the dependency that failed if any.
+fn load_error_ui(file_path: &str, load_error: &LoadError, ui: &mut egui::Ui) {
+    if !load_error.file_path.eq_ignore_ascii_case(file_path) {
+        ui.colored_label(
+            egui::Color32::RED,
+            format!("✖ Failed dependency: {}", load_error.file_path),
+        );
     }
+    ui.colored_label(egui::Color32::RED, &load_error.message);
 }
@@ -15,17 +15,31 @@ use bevy_inspector_egui::{
 };
 
 use crate::{
-    assets::{geoset::*, material::TerrainMaterial, model::*, world_model::WorldModel},
-    data::{CurrentFile, file::FileInfoMap},
-    ui::get_file_icon,
+    assets::{
+        geoset::*, material::TerrainMaterial, model::*, raw::RawAsset, world_model::WorldModel,
+    },
+    comparison::{Comparison, ComparisonStats},
+    data::{
+        CurrentFile, SceneFile,
+        file::{DataType, FileInfoMap},
+        load_error::LoadErrorMap,
+        references::ReferenceIndex,
+    },
+    export::{EXPORT_PATH, ExportFormat, ExportRequested, ExportStatus, ExportTasks},
+    ui::{FileSelected, get_file_icon, load_error_ui, texture_inspector::texture_inspector_ui},
 };
 
 pub fn ui(world: &mut World, context: &mut EguiContext) -> egui::InnerResponse<()> {
     let side_panel = egui::SidePanel::right("current_file_panel");
 
     let mut file_path = None;
-    if let Ok(current_file) = world.query::<&CurrentFile>().single(world) {
-        file_path.replace(current_file.path.clone());
+    let mut comparison = false;
+    if let Ok(scene_file) = world
+        .query_filtered::<&SceneFile, With<CurrentFile>>()
+        .single(world)
+    {
+        file_path.replace(scene_file.path.clone());
+        comparison = scene_file.comparison;
     }
 
     let mut label = None;
@@ -33,14 +47,21 @@ pub fn ui(world: &mut World, context: &mut EguiContext) -> egui::InnerResponse<(
         && let Some(file_info_map) = world.get_resource::<FileInfoMap>()
         && let Ok(file_info) = file_info_map.get_file(&file_path)
     {
-        label.replace(format!(
-            "{} {}",
-            get_file_icon(&file_info.data_type),
-            file_path
+        let load_error = world
+            .get_resource::<LoadErrorMap>()
+            .zip(world.get_resource::<AssetServer>())
+            .and_then(|(load_error_map, asset_server)| {
+                load_error_map.get_file_error(file_info, asset_server)
+            });
+        label.replace((
+            format!("{} {}", get_file_icon(&file_info.data_type), file_path),
+            file_info.data_type.clone(),
+            file_path,
+            load_error,
         ));
     }
 
-    if let Some(label) = label {
+    if let Some((label, data_type, file_path, load_error)) = label {
         side_panel
             .resizable(true)
             .min_width(240.0)
@@ -53,6 +74,17 @@ pub fn ui(world: &mut World, context: &mut EguiContext) -> egui::InnerResponse<(
                     .id_salt("current_file_scroll")
                     .show(ui, |ui| {
                         ui.label(label);
+                        if let Some(load_error) = load_error {
+                            load_error_ui(&file_path, &load_error, ui);
+                            ui.separator();
+                        }
+                        export_ui(world, ui);
+                        comparison_ui(world, ui);
+                        raw_file_ui(world, &data_type, ui);
+                        if let DataType::Texture(_) = data_type {
+                            texture_inspector_ui(world, &file_path, comparison, ui);
+                        }
+                        references_ui(world, &file_path, ui);
                         ui_for_entities_filtered(
                             world,
                             ui,
@@ -72,6 +104,244 @@ pub fn ui(world: &mut World, context: &mut EguiContext) -> egui::InnerResponse<(
     }
 }
 
+/// Buttons exporting the current file to the `export` folder, and the outcome
+/// of the last export.
+fn export_ui(world: &mut World, ui: &mut egui::Ui) {
+    let Ok(entity) = world
+        .query_filtered::<Entity, With<CurrentFile>>()
+        .single(world)
+    else {
+        return;
+    };
+
+    let mut requested = None;
+    ui.horizontal(|ui| {
+        ui.label("💾 Export:");
+        for format in ExportFormat::ALL {
+            if ui
+                .button(format.name())
+                .on_hover_text(format!("Export to the {EXPORT_PATH} folder"))
+                .clicked()
+            {
+                requested.replace(ExportRequested::new(entity, format));
+            }
+        }
+    });
+    if !world.resource::<ExportTasks>().is_empty() {
+        ui.horizontal(|ui| {
+            ui.spinner();
+            ui.label("Exporting…");
+        });
+    }
+    match &world.resource::<ExportStatus>().last {
+        Some(Ok(path)) => {
+            ui.weak(format!("Exported to {}", path.display()));
+        }
+        Some(Err(err)) => {
+            ui.colored_label(egui::Color32::RED, format!("Export failed: {err}"));
+        }
+        None => {}
+    }
+    ui.separator();
+
+    if let Some(message) = requested {
+        world.write_message(message);
+    }
+}
+
+/// Differences between the two files compared side by side: their geometry
+/// counts and their textures.
+fn comparison_ui(world: &World, ui: &mut egui::Ui) {
+    let comparison = world.resource::<Comparison>();
+    if !comparison.enabled {
+        return;
+    }
+    let (Some(left), Some(right)) = (comparison.left, comparison.right) else {
+        ui.weak("Add another file to the scene to compare it");
+        ui.separator();
+        return;
+    };
+    let get_name = |entity: Entity| match world.get::<SceneFile>(entity) {
+        Some(scene_file) if scene_file.comparison => {
+            format!("{} (comparison installation)", scene_file.path)
+        }
+        Some(scene_file) => scene_file.path.clone(),
+        None => String::new(),
+    };
+    let (left_name, right_name) = (get_name(left), get_name(right));
+    let stats = world.resource::<ComparisonStats>();
+    let (left_stats, right_stats) = (&stats.left, &stats.right);
+
+    egui::CollapsingHeader::new("⚖ Comparison")
+        .default_open(true)
+        .show(ui, |ui| {
+            ui.label(format!("A: {left_name}"));
+            ui.label(format!("B: {right_name}"));
+            egui::Grid::new("comparison_grid")
+                .num_columns(4)
+                .striped(true)
+                .show(ui, |ui| {
+                    ui.strong("");
+                    ui.strong("A");
+                    ui.strong("B");
+                    ui.strong("Δ");
+                    ui.end_row();
+                    let rows = [
+                        ("Meshes", left_stats.mesh_count, right_stats.mesh_count),
+                        (
+                            "Vertices",
+                            left_stats.vertex_count,
+                            right_stats.vertex_count,
+                        ),
+                        (
+                            "Triangles",
+                            left_stats.triangle_count,
+                            right_stats.triangle_count,
+                        ),
+                        (
+                            "Textures",
+                            left_stats.textures.len(),
+                            right_stats.textures.len(),
+                        ),
+                    ];
+                    for (name, a, b) in rows {
+                        ui.label(name);
+                        ui.label(a.to_string());
+                        ui.label(b.to_string());
+                        let delta = b as i64 - a as i64;
+                        if delta == 0 {
+                            ui.weak("=");
+                        } else {
+                            ui.label(format!("{delta:+}"));
+                        }
+                        ui.end_row();
+                    }
+                });
+
+            let only_left: Vec<_> = left_stats
+                .textures
+                .iter()
+                .filter(|texture| !right_stats.textures.contains(texture))
+                .collect();
+            let only_right: Vec<_> = right_stats
+                .textures
+                .iter()
+                .filter(|texture| !left_stats.textures.contains(texture))
+                .collect();
+            let shared_count = left_stats.textures.len() - only_left.len();
+            for (title, textures) in [("Only in A", only_left), ("Only in B", only_right)] {
+                egui::CollapsingHeader::new(format!("{title} ({})", textures.len()))
+                    .default_open(false)
+                    .show(ui, |ui| {
+                        for texture in textures {
+                            ui.label(texture);
+                        }
+                    });
+            }
+            ui.label(format!("Shared textures: {shared_count}"));
+        });
+    ui.separator();
+}
+
+/// Generic view of the files without a dedicated loader: their size, and a
+/// preview of their first lines or bytes.
+fn raw_file_ui(world: &World, data_type: &DataType, ui: &mut egui::Ui) {
+    const PREVIEW_LINE_COUNT: usize = 64;
+    const BYTES_PER_LINE: usize = 16;
+
+    let DataType::Raw(_, handle) = data_type else {
+        return;
+    };
+    let Some(raw_asset) = world.resource::<Assets<RawAsset>>().get(handle) else {
+        return;
+    };
+
+    ui.label(format!("Size: {} bytes", raw_asset.bytes.len()));
+    let preview = if let Ok(text) = std::str::from_utf8(&raw_asset.bytes) {
+        text.lines()
+            .take(PREVIEW_LINE_COUNT)
+            .collect::<Vec<_>>()
+            .join("\n")
+    } else {
+        raw_asset
+            .bytes
+            .chunks(BYTES_PER_LINE)
+            .take(PREVIEW_LINE_COUNT)
+            .enumerate()
+            .map(|(index, chunk)| hex_line(index * BYTES_PER_LINE, chunk))
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+    ui.label(egui::RichText::new(preview).monospace());
+    ui.separator();
+}
+
+/// Lists the files referencing the current file and the files it references.
+fn references_ui(world: &mut World, file_path: &str, ui: &mut egui::Ui) {
+    let Some(index) = world.get_resource::<ReferenceIndex>() else {
+        return;
+    };
+    let file_info_map = world.get_resource::<FileInfoMap>();
+
+    if !index.is_done() {
+        ui.weak(format!(
+            "Scanning references… {}/{}",
+            index.scanned_count, index.file_count
+        ));
+    }
+
+    let mut message = None;
+    let lists = [
+        ("Referenced by", index.get_referenced_by(file_path)),
+        (
+            "References",
+            index.get_references(file_path).iter().collect(),
+        ),
+    ];
+    for (label, paths) in lists {
+        egui::CollapsingHeader::new(format!("{} ({})", label, paths.len()))
+            .id_salt(label)
+            .default_open(true)
+            .enabled(!paths.is_empty())
+            .show(ui, |ui| {
+                for path in paths {
+                    let file_info = file_info_map.and_then(|map| map.get_file(path).ok());
+                    let Some(file_info) = file_info else {
+                        ui.colored_label(egui::Color32::RED, format!("✖ {}", path))
+                            .on_hover_text("Missing from the archives");
+                        continue;
+                    };
+                    let label =
+                        format!("{} {}", get_file_icon(&file_info.data_type), file_info.path);
+                    if ui.selectable_label(false, label).clicked() {
+                        message.replace(FileSelected::new(file_info.path.clone()));
+                    }
+                }
+            });
+    }
+    ui.separator();
+
+    if let Some(message) = message {
+        world.write_message(message);
+    }
+}
+
+/// Formats bytes as `offset  hex bytes  ascii`.
+fn hex_line(offset: usize, bytes: &[u8]) -> String {
+    let hex: Vec<String> = bytes.iter().map(|byte| format!("{:02x}", byte)).collect();
+    let ascii: String = bytes
+        .iter()
+        .map(|&byte| {
+            if byte.is_ascii_graphic() || byte == b' ' {
+                byte as char
+            } else {
+                '.'
+            }
+        })
+        .collect();
+    format!("{:08x}  {:<47}  {}", offset, hex.join(" "), ascii)
+}
+
 impl InspectorPrimitive for Model {
     fn ui(
         &mut self,
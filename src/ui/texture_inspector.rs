@@ -0,0 +1,305 @@
+// Copyright © 2025
+// Author: Nocthir <nocthir@proton.me>
+// SPDX-License-Identifier: MIT or Apache-2.0
+
+//! Texture inspector of the BLP files.
+//!
+//! The file is read again from the archives and decoded on the CPU, so every
+//! mip level can be shown whatever the format uploaded to the GPU, with its
+//! channels isolated and a checkerboard behind the transparent pixels. The
+//! decoding runs on the async compute task pool, so large files do not stall
+//! the interface.
+
+use bevy::prelude::*;
+use bevy::tasks::{self, Task};
+use bevy_egui::egui;
+use image::RgbaImage;
+
+use crate::assets::{
+    archive::{ArchiveAssetReader, ArchiveRegistry, ComparisonRegistry},
+    image::BlpInspection,
+};
+
+const CHANNEL_NAMES: [&str; 4] = ["R", "G", "B", "A"];
+/// Size in points of a checkerboard square.
+const CHECKER_SIZE: f32 = 8.0;
+
+/// Identifies the file an inspection has been done for. The same path may be
+/// read from the comparison installation, or from another installation after a
+/// game switch.
+#[derive(Clone, PartialEq, Eq)]
+struct InspectedFile {
+    file_path: String,
+    comparison: bool,
+    /// Generation of the archive registry the file has been read from.
+    generation: u64,
+}
+
+#[derive(Resource)]
+pub struct TextureInspector {
+    inspected: Option<InspectedFile>,
+    /// Decoding of the inspected file, until it is done.
+    task: Option<Task<Result<BlpInspection, String>>>,
+    inspection: Option<Result<BlpInspection, String>>,
+    level: usize,
+    channels: [bool; 4],
+    zoom: f32,
+    texture: Option<egui::TextureHandle>,
+    /// Level and channels the texture has been composited for.
+    texture_key: Option<(usize, [bool; 4])>,
+    checkerboard: Option<egui::TextureHandle>,
+}
+
+impl Default for TextureInspector {
+    fn default() -> Self {
+        Self {
+            inspected: None,
+            task: None,
+            inspection: None,
+            level: 0,
+            channels: [true; 4],
+            zoom: 1.0,
+            texture: None,
+            texture_key: None,
+            checkerboard: None,
+        }
+    }
+}
+
+impl TextureInspector {
+    /// Starts decoding a new file, keeping the view settings. Replacing a
+    /// previous task drops it.
+    fn inspect(&mut self, inspected: InspectedFile, registry: ArchiveRegistry) {
+        let file_path = inspected.file_path.clone();
+        let task = tasks::AsyncComputeTaskPool::get().spawn(async move {
+            let reader = ArchiveAssetReader::new(registry);
+            reader
+                .read_file(&file_path)
+                .map_err(|err| err.to_string())
+                .and_then(|bytes| BlpInspection::new(&bytes).map_err(|err| err.to_string()))
+        });
+        self.inspected = Some(inspected);
+        self.task = Some(task);
+        self.inspection = None;
+        self.level = 0;
+        self.texture_key = None;
+    }
+
+    /// Takes the result of the decoding task once it is done.
+    fn check_task(&mut self) {
+        let Some(task) = &mut self.task else {
+            return;
+        };
+        if let Some(inspection) = tasks::block_on(tasks::poll_once(task)) {
+            self.inspection = Some(inspection);
+            self.task = None;
+        }
+    }
+
+    /// Composites the current level again when the level or channels changed.
+    fn update_texture(&mut self, ctx: &egui::Context) {
+        let Some(Ok(inspection)) = &self.inspection else {
+            return;
+        };
+        let key = (self.level, self.channels);
+        if self.texture_key == Some(key) {
+            return;
+        }
+        let image = composite(&inspection.mips[self.level], self.channels);
+        match &mut self.texture {
+            Some(texture) => texture.set(image, egui::TextureOptions::NEAREST),
+            None => {
+                self.texture = Some(ctx.load_texture(
+                    "texture_inspector",
+                    image,
+                    egui::TextureOptions::NEAREST,
+                ))
+            }
+        }
+        self.texture_key = Some(key);
+    }
+
+    fn get_checkerboard(&mut self, ctx: &egui::Context) -> egui::TextureId {
+        self.checkerboard
+            .get_or_insert_with(|| {
+                let (light, dark) = ([204, 204, 204, 255], [153, 153, 153, 255]);
+                let image = egui::ColorImage::from_rgba_unmultiplied(
+                    [2, 2],
+                    &[light, dark, dark, light].concat(),
+                );
+                ctx.load_texture(
+                    "texture_inspector_checkerboard",
+                    image,
+                    egui::TextureOptions::NEAREST_REPEAT,
+                )
+            })
+            .id()
+    }
+}
+
+/// Zoomable view of a mip level of the current BLP file, with its header
+/// information. `comparison` tells whether the file comes from the comparison
+/// installation.
+pub fn texture_inspector_ui(
+    world: &mut World,
+    file_path: &str,
+    comparison: bool,
+    ui: &mut egui::Ui,
+) {
+    let registry = if comparison {
+        world.resource::<ComparisonRegistry>().0.clone()
+    } else {
+        world.resource::<ArchiveRegistry>().clone()
+    };
+    let inspected = InspectedFile {
+        file_path: file_path.to_string(),
+        comparison,
+        generation: registry.generation(),
+    };
+    let mut inspector = world.resource_mut::<TextureInspector>();
+    let inspector = inspector.bypass_change_detection();
+    if inspector.inspected.as_ref() != Some(&inspected) {
+        inspector.inspect(inspected, registry);
+    }
+    inspector.check_task();
+
+    egui::CollapsingHeader::new("🖼 Texture")
+        .default_open(true)
+        .show(ui, |ui| {
+            let Some(Ok(inspection)) = &inspector.inspection else {
+                match &inspector.inspection {
+                    Some(Err(err)) => {
+                        ui.colored_label(egui::Color32::RED, format!("Failed to decode: {err}"));
+                    }
+                    _ => {
+                        ui.horizontal(|ui| {
+                            ui.spinner();
+                            ui.label("Decoding…");
+                        });
+                    }
+                }
+                return;
+            };
+            info_ui(inspection, ui);
+            controls_ui(inspector, ui);
+            image_ui(inspector, ui);
+        });
+    ui.separator();
+}
+
+fn info_ui(inspection: &BlpInspection, ui: &mut egui::Ui) {
+    let info = &inspection.info;
+    egui::Grid::new("texture_info")
+        .num_columns(2)
+        .striped(true)
+        .show(ui, |ui| {
+            ui.label("Compression");
+            ui.label(info.compression);
+            ui.end_row();
+            ui.label("Alpha bits");
+            ui.label(info.alpha_bits.to_string());
+            ui.end_row();
+            ui.label("Dimensions");
+            ui.label(format!("{}x{}", info.width, info.height));
+            ui.end_row();
+            ui.label("Mip levels");
+            ui.label(format!(
+                "{} stored, {} total",
+                info.mip_count,
+                inspection.mips.len()
+            ));
+            ui.end_row();
+            ui.label("Palette");
+            ui.label(if info.has_palette { "Yes" } else { "No" });
+            ui.end_row();
+        });
+}
+
+fn controls_ui(inspector: &mut TextureInspector, ui: &mut egui::Ui) {
+    let mip_count = inspector
+        .inspection
+        .as_ref()
+        .and_then(|inspection| inspection.as_ref().ok())
+        .map_or(1, |inspection| inspection.mips.len());
+    ui.add(
+        egui::Slider::new(&mut inspector.zoom, 0.125..=8.0)
+            .logarithmic(true)
+            .text("Zoom"),
+    );
+    ui.add_enabled(
+        mip_count > 1,
+        egui::Slider::new(&mut inspector.level, 0..=mip_count - 1).text("Mip level"),
+    );
+    ui.horizontal(|ui| {
+        ui.label("Channels:");
+        for (channel, name) in inspector.channels.iter_mut().zip(CHANNEL_NAMES) {
+            ui.toggle_value(channel, name);
+        }
+        if ui.button("All").clicked() {
+            inspector.channels = [true; 4];
+        }
+    });
+}
+
+fn image_ui(inspector: &mut TextureInspector, ui: &mut egui::Ui) {
+    inspector.update_texture(ui.ctx());
+    let checkerboard = inspector.get_checkerboard(ui.ctx());
+    let Some(texture) = &inspector.texture else {
+        return;
+    };
+
+    let size = texture.size_vec2() * inspector.zoom;
+    let (rect, response) = ui.allocate_exact_size(size, egui::Sense::hover());
+    let painter = ui.painter_at(rect);
+    // One checkerboard texel per square
+    let checker_uv = egui::Rect::from_min_size(egui::Pos2::ZERO, size / (2.0 * CHECKER_SIZE));
+    painter.image(checkerboard, rect, checker_uv, egui::Color32::WHITE);
+    let uv = egui::Rect::from_min_max(egui::Pos2::ZERO, egui::pos2(1.0, 1.0));
+    painter.image(texture.id(), rect, uv, egui::Color32::WHITE);
+
+    let [width, height] = texture.size();
+    response.on_hover_text(format!("{width}x{height}"));
+}
+
+/// Composites the enabled channels of an image. A single channel is shown in
+/// grayscale, and the image is opaque when the alpha channel is disabled.
+fn composite(image: &RgbaImage, channels: [bool; 4]) -> egui::ColorImage {
+    let size = [image.width() as usize, image.height() as usize];
+    let enabled: Vec<usize> = (0..4).filter(|&i| channels[i]).collect();
+    let rgba: Vec<u8> = image
+        .pixels()
+        .flat_map(|pixel| {
+            if let [channel] = enabled[..] {
+                let value = pixel.0[channel];
+                return [value, value, value, u8::MAX];
+            }
+            let [r, g, b, a] = pixel.0;
+            let mask = |value: u8, channel: usize| if channels[channel] { value } else { 0 };
+            let alpha = if channels[3] { a } else { u8::MAX };
+            [mask(r, 0), mask(g, 1), mask(b, 2), alpha]
+        })
+        .collect();
+    egui::ColorImage::from_rgba_unmultiplied(size, &rgba)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_composite() {
+        let image = RgbaImage::from_raw(1, 1, vec![10, 20, 30, 40]).unwrap();
+
+        let gray = composite(&image, [false, true, false, false]);
+        assert_eq!(gray.pixels[0], egui::Color32::from_gray(20));
+
+        let alpha = composite(&image, [false, false, false, true]);
+        assert_eq!(alpha.pixels[0], egui::Color32::from_gray(40));
+
+        let opaque = composite(&image, [true, false, true, false]);
+        assert_eq!(
+            opaque.pixels[0],
+            egui::Color32::from_rgba_unmultiplied(10, 0, 30, 255)
+        );
+    }
+}